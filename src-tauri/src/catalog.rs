@@ -0,0 +1,188 @@
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use rusqlite::{params_from_iter, types::Value, Connection};
+use serde::Serialize;
+
+use crate::data::strip_extension;
+use crate::db;
+use crate::error::AppError;
+use crate::query::normalize_name;
+
+fn open_db() -> Result<Connection, AppError> {
+    db::open().map_err(|e| AppError::db(format!("Failed to open DB: {}", e)))
+}
+
+#[derive(Serialize)]
+struct CatalogRow {
+    name: String,
+    console: String,
+    size: String,
+    size_bytes: Option<i64>,
+    dl_link: String,
+    is_downloaded: bool,
+}
+
+/// Writes every game (or one console's) to `dest` as `csv` or `json`.
+/// Rows are streamed straight from the query. Returns how many were written.
+#[tauri::command]
+pub fn export_catalog(format: String, dest: String, console: Option<String>) -> Result<usize, AppError> {
+    let format = format.to_ascii_lowercase();
+    if format != "csv" && format != "json" {
+        return Err(AppError::invalid_input(format!("Unknown export format {:?} (expected csv or json)", format)));
+    }
+    if dest.trim().is_empty() {
+        return Err(AppError::invalid_input("Export path cannot be empty"));
+    }
+
+    let conn = open_db()?;
+
+    let mut sql = String::from(
+        "SELECT name, console, size, size_bytes, dl_link, is_downloaded FROM games",
+    );
+    let mut params: Vec<Value> = Vec::new();
+    if let Some(console) = console.filter(|c| !c.trim().is_empty()) {
+        sql.push_str(" WHERE console = ?");
+        params.push(Value::Text(console));
+    }
+    sql.push_str(" ORDER BY console, name");
+
+    let mut stmt = conn
+        .prepare(&sql)
+        .map_err(|e| AppError::db(format!("Failed to prepare query: {}", e)))?;
+    let rows = stmt
+        .query_map(params_from_iter(params), |row| {
+            Ok(CatalogRow {
+                name: row.get(0)?,
+                console: row.get(1)?,
+                size: row.get(2)?,
+                size_bytes: row.get(3)?,
+                dl_link: row.get(4)?,
+                is_downloaded: row.get::<_, i64>(5)? != 0,
+            })
+        })
+        .map_err(|e| AppError::db(format!("Query execution failed: {}", e)))?;
+
+    let file = File::create(&dest).map_err(|e| AppError::io(format!("Failed to create {}: {}", dest, e)))?;
+    let mut out = BufWriter::new(file);
+    let mut count = 0;
+
+    if format == "csv" {
+        let mut writer = csv::Writer::from_writer(out);
+        for row in rows {
+            let row = row.map_err(|e| AppError::db(format!("Row error: {}", e)))?;
+            writer
+                .serialize(&row)
+                .map_err(|e| AppError::io(format!("Failed to write CSV: {}", e)))?;
+            count += 1;
+        }
+        writer.flush().map_err(|e| AppError::io(format!("Failed to write CSV: {}", e)))?;
+    } else {
+        // One element at a time instead of collecting a Vec for serde_json
+        let write_err = |e: std::io::Error| AppError::io(format!("Failed to write JSON: {}", e));
+        out.write_all(b"[\n").map_err(write_err)?;
+        for row in rows {
+            let row = row.map_err(|e| AppError::db(format!("Row error: {}", e)))?;
+            if count > 0 {
+                out.write_all(b",\n").map_err(write_err)?;
+            }
+            serde_json::to_writer(&mut out, &row)
+                .map_err(|e| AppError::io(format!("Failed to write JSON: {}", e)))?;
+            count += 1;
+        }
+        out.write_all(b"\n]\n").map_err(write_err)?;
+        out.flush().map_err(write_err)?;
+    }
+
+    Ok(count)
+}
+
+#[derive(Serialize, Debug, Default)]
+pub struct ImportSummary {
+    pub matched: usize,
+    pub unmatched_count: usize,
+    pub unmatched: Vec<String>,
+}
+
+/// Names from an owned-games list: one per line, or the first column of a `.csv`
+/// (so a file from `export_catalog` works). Plain lists aren't split on commas
+/// because names like "(USA, Europe)" contain them.
+fn read_owned_names(path: &Path) -> Result<Vec<String>, AppError> {
+    let is_csv = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| e.eq_ignore_ascii_case("csv"));
+
+    let mut names = Vec::new();
+    if is_csv {
+        let mut reader = csv::ReaderBuilder::new()
+            .has_headers(false)
+            .flexible(true)
+            .from_path(path)
+            .map_err(|e| AppError::io(format!("Failed to open {:?}: {}", path, e)))?;
+        for (i, record) in reader.records().enumerate() {
+            let record = record.map_err(|e| AppError::invalid_input(format!("Bad CSV line {}: {}", i + 1, e)))?;
+            let Some(name) = record.get(0) else { continue };
+            if i == 0 && name.eq_ignore_ascii_case("name") {
+                continue; // header
+            }
+            names.push(name.to_string());
+        }
+    } else {
+        let text = fs::read_to_string(path).map_err(|e| AppError::io(format!("Failed to read {:?}: {}", path, e)))?;
+        names.extend(text.lines().map(|l| l.to_string()));
+    }
+
+    Ok(names
+        .into_iter()
+        .map(|n| n.trim().to_string())
+        .filter(|n| !n.is_empty())
+        .collect())
+}
+
+/// Marks games from an owned-games list as downloaded. Names are matched the way
+/// `search_games` normalizes them, ignoring an archive extension like `.zip`.
+#[tauri::command]
+pub fn import_owned_list(path: String) -> Result<ImportSummary, AppError> {
+    let names = read_owned_names(Path::new(&path))?;
+    let conn = open_db()?;
+
+    // The same name can exist for several consoles; mark all of them
+    let mut by_name: HashMap<String, Vec<i64>> = HashMap::new();
+    {
+        let mut stmt = conn
+            .prepare("SELECT id, name FROM games")
+            .map_err(|e| AppError::db(format!("Failed to prepare query: {}", e)))?;
+        let rows = stmt
+            .query_map([], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?)))
+            .map_err(|e| AppError::db(format!("Query execution failed: {}", e)))?;
+        for row in rows {
+            let (id, name) = row.map_err(|e| AppError::db(format!("Row error: {}", e)))?;
+            by_name.entry(normalize_name(&name)).or_default().push(id);
+        }
+    }
+
+    let tx = conn
+        .unchecked_transaction()
+        .map_err(|e| AppError::db(format!("Failed to start transaction: {}", e)))?;
+    let mut summary = ImportSummary::default();
+
+    for name in names {
+        match by_name.get(&normalize_name(strip_extension(&name))) {
+            Some(ids) => {
+                for id in ids {
+                    tx.execute("UPDATE games SET is_downloaded = 1 WHERE id = ?1", [id])
+                        .map_err(|e| AppError::db(format!("Failed to update is_downloaded: {}", e)))?;
+                }
+                summary.matched += 1;
+            }
+            None => summary.unmatched.push(name),
+        }
+    }
+
+    tx.commit().map_err(|e| AppError::db(format!("Failed to commit: {}", e)))?;
+    summary.unmatched_count = summary.unmatched.len();
+    Ok(summary)
+}
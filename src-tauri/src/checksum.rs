@@ -0,0 +1,170 @@
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+use md5::Md5;
+use serde::Serialize;
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+use tokio::task;
+
+use crate::error::AppError;
+
+/// Hashes No-Intro/Redump DATs list; which ones a DAT carries depends on the source.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum HashAlgorithm {
+    Crc32,
+    Md5,
+    Sha1,
+    Sha256,
+}
+
+impl HashAlgorithm {
+    pub const ALL: [HashAlgorithm; 4] = [
+        HashAlgorithm::Crc32,
+        HashAlgorithm::Md5,
+        HashAlgorithm::Sha1,
+        HashAlgorithm::Sha256,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            HashAlgorithm::Crc32 => "crc32",
+            HashAlgorithm::Md5 => "md5",
+            HashAlgorithm::Sha1 => "sha1",
+            HashAlgorithm::Sha256 => "sha256",
+        }
+    }
+
+    pub fn parse(name: &str) -> Result<HashAlgorithm, AppError> {
+        let name = name.trim();
+        Self::ALL
+            .into_iter()
+            .find(|a| a.as_str().eq_ignore_ascii_case(name))
+            .ok_or_else(|| {
+                AppError::invalid_input(format!(
+                    "Unknown hash algorithm {:?}; expected crc32, md5, sha1 or sha256",
+                    name
+                ))
+            })
+    }
+}
+
+// What archives record per entry, so what extracted files can be checked against
+pub const DEFAULT_HASH_ALGORITHM: HashAlgorithm = HashAlgorithm::Crc32;
+
+enum Hasher {
+    Crc32(crc32fast::Hasher),
+    Md5(Md5),
+    Sha1(Sha1),
+    Sha256(Sha256),
+}
+
+impl Hasher {
+    fn new(algorithm: HashAlgorithm) -> Self {
+        match algorithm {
+            HashAlgorithm::Crc32 => Hasher::Crc32(crc32fast::Hasher::new()),
+            HashAlgorithm::Md5 => Hasher::Md5(Md5::new()),
+            HashAlgorithm::Sha1 => Hasher::Sha1(Sha1::new()),
+            HashAlgorithm::Sha256 => Hasher::Sha256(Sha256::new()),
+        }
+    }
+
+    fn update(&mut self, bytes: &[u8]) {
+        match self {
+            Hasher::Crc32(h) => h.update(bytes),
+            Hasher::Md5(h) => h.update(bytes),
+            Hasher::Sha1(h) => h.update(bytes),
+            Hasher::Sha256(h) => h.update(bytes),
+        }
+    }
+
+    /// Lowercase hex, the way DATs write them (CRC32 as eight digits).
+    fn finish(self) -> String {
+        let bytes = match self {
+            Hasher::Crc32(h) => h.finalize().to_be_bytes().to_vec(),
+            Hasher::Md5(h) => h.finalize().to_vec(),
+            Hasher::Sha1(h) => h.finalize().to_vec(),
+            Hasher::Sha256(h) => h.finalize().to_vec(),
+        };
+        bytes.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+}
+
+// Read size when hashing; files are streamed, never loaded whole
+const HASH_BUFFER_BYTES: usize = 256 * 1024;
+
+/// Hashes `reader` to the end with `algorithm`, passing `on_read` the bytes hashed so far
+/// after every read.
+fn hash_reader(mut reader: impl Read, algorithm: HashAlgorithm, mut on_read: impl FnMut(u64)) -> std::io::Result<String> {
+    let mut hasher = Hasher::new(algorithm);
+    let mut buffer = vec![0u8; HASH_BUFFER_BYTES];
+    let mut hashed = 0u64;
+    loop {
+        let n = reader.read(&mut buffer)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buffer[..n]);
+        hashed += n as u64;
+        on_read(hashed);
+    }
+    Ok(hasher.finish())
+}
+
+/// Hashes the file at `path` with `algorithm`, as lowercase hex.
+pub fn compute_hash(path: &Path, algorithm: HashAlgorithm) -> Result<String, AppError> {
+    compute_hash_with_progress(path, algorithm, |_| {})
+}
+
+/// `compute_hash`, passing `on_read` the bytes hashed so far as it goes.
+pub fn compute_hash_with_progress(
+    path: &Path,
+    algorithm: HashAlgorithm,
+    on_read: impl FnMut(u64),
+) -> Result<String, AppError> {
+    let file = File::open(path).map_err(|e| AppError::io(format!("Failed to open {:?}: {}", path, e)))?;
+    hash_reader(file, algorithm, on_read).map_err(|e| AppError::io(format!("Failed to read {:?}: {}", path, e)))
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct FileHash {
+    pub algorithm: String,
+    pub hash: String,
+}
+
+/// Hashes a file with `algorithm`, or CRC32 if it's left out.
+#[tauri::command]
+pub async fn compute_file_hash(path: String, algorithm: Option<String>) -> Result<FileHash, AppError> {
+    let hash_task = task::spawn_blocking(move || -> Result<FileHash, AppError> {
+        let algorithm = match algorithm {
+            Some(name) => HashAlgorithm::parse(&name)?,
+            None => DEFAULT_HASH_ALGORITHM,
+        };
+        Ok(FileHash {
+            algorithm: algorithm.as_str().to_string(),
+            hash: compute_hash(Path::new(&path), algorithm)?,
+        })
+    });
+
+    hash_task.await.map_err(|e| AppError::internal(e.to_string()))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn known_digests_of_a_short_input() {
+        let hash = |algorithm| hash_reader(&b"The quick brown fox jumps over the lazy dog"[..], algorithm, |_| {}).unwrap();
+
+        assert_eq!(hash(HashAlgorithm::Crc32), "414fa339");
+        assert_eq!(hash(HashAlgorithm::Sha1), "2fd4e1c67a2d28fced849ee1bb76e7391b93eb12");
+        assert_eq!(
+            hash(HashAlgorithm::Sha256),
+            "d7a8fbb307d7809469ca9abcb0082e4f8d5651e46d3cdb762d02d0bf37c9e592"
+        );
+        assert_eq!(HashAlgorithm::parse(" SHA256 ").unwrap(), HashAlgorithm::Sha256);
+        assert!(HashAlgorithm::parse("whirlpool").is_err());
+    }
+}
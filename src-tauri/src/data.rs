@@ -2,160 +2,904 @@
 
 // Scraper dependencies
 use reqwest;
+use regex::Regex;
+use reqwest::blocking::Client;
+use reqwest::header::HeaderMap;
 use scraper::{Html, Selector};
 use rusqlite::{params, Connection, Result};
 
 // Std dependencies
+use std::collections::BTreeMap;
 use std::fs;
-use std::path::PathBuf;
-use dirs;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+use crate::error::AppError;
+use crate::net::{apply_mirror, connection_permit, parse_headers, scrape_client};
+use crate::db;
+use crate::download::{self, format_bytes};
+use crate::history::now_unix;
+use crate::maintenance;
+use crate::paths::db_path;
+use crate::query::normalize_name;
+use crate::settings::read_u64_setting;
 
 // ------------------------ Data Struct ------------------------
 
+#[derive(serde::Serialize)]
 pub struct Game {
     pub name: String,
     pub date: String,
     pub size: String,
+    pub size_bytes: Option<i64>,
+    pub extension: Option<String>,
     pub dl_link: String,
     pub is_downloaded: bool,
+    pub parts: Vec<String>, // every part's link, in order, for a split archive; empty otherwise
 }
 
 // ------------------------ DB Helpers ------------------------
 
-pub fn db_path() -> PathBuf {
-    let mut p = dirs::home_dir()
-        .expect("Could not determine home directory");
+/// Upserts by (name, console); an existing row keeps its `is_downloaded` flag and `first_seen`.
+/// A new row takes its `first_seen` from `seen_before` when a full rebuild snapshotted one.
+pub fn save_to_db(conn: &Connection, game: &Game, console: &str) -> Result<()> {
+    conn.execute(
+        "INSERT INTO games (name, console, date, size, size_bytes, extension, dl_link, is_downloaded, available, clean_name, date_unix, first_seen)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, 1, ?9, ?10,
+                 COALESCE((SELECT first_seen FROM seen_before WHERE name = ?1 AND console = ?2), ?11))
+         ON CONFLICT(name, console) DO UPDATE SET
+            clean_name = excluded.clean_name,
+            date = excluded.date,
+            date_unix = excluded.date_unix,
+            size = excluded.size,
+            size_bytes = excluded.size_bytes,
+            extension = excluded.extension,
+            dl_link = excluded.dl_link,
+            available = 1",
+        params![
+            game.name,
+            console,
+            game.date,
+            game.size,
+            game.size_bytes,
+            game.extension,
+            game.dl_link,
+            game.is_downloaded,
+            clean_name(&game.name),
+            parse_listing_date(&game.date),
+            now_unix(),
+        ],
+    )?;
 
-    p.push(".rusty-roms");
+    if !game.parts.is_empty() {
+        let id: i64 = conn.query_row(
+            "SELECT id FROM games WHERE name = ?1 AND console = ?2",
+            params![game.name, console],
+            |row| row.get(0),
+        )?;
+        conn.execute("DELETE FROM game_parts WHERE game_id = ?1", [id])?;
+        for (i, url) in game.parts.iter().enumerate() {
+            conn.execute(
+                "INSERT INTO game_parts (game_id, part, url) VALUES (?1, ?2, ?3)",
+                params![id, i as i64 + 1, url],
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// Links of a split archive's parts in order; empty for an ordinary one-file game.
+pub fn game_parts(conn: &Connection, id: i64) -> Result<Vec<String>> {
+    conn.prepare("SELECT url FROM game_parts WHERE game_id = ?1 ORDER BY part")?
+        .query_map([id], |row| row.get(0))?
+        .collect()
+}
 
-    std::fs::create_dir_all(&p)
-        .expect("Failed to create .rusty-roms directory");
+/// Primary title: the name without its extension and `(...)`/`[...]` tags,
+/// e.g. "Legend of Zelda, The (USA) [b].zip" -> "Legend of Zelda, The".
+pub fn clean_name(name: &str) -> String {
+    let mut out = String::new();
+    let mut depth = 0usize;
+    for c in strip_extension(name).chars() {
+        match c {
+            '(' | '[' => depth += 1,
+            ')' | ']' => depth = depth.saturating_sub(1),
+            _ if depth == 0 => out.push(c),
+            _ => {}
+        }
+    }
 
-    p.push("games.db");
-    p
+    let cleaned = out.split_whitespace().collect::<Vec<_>>().join(" ");
+    if cleaned.is_empty() {
+        strip_extension(name).trim().to_string()
+    } else {
+        cleaned
+    }
 }
 
+// Multi-dot extensions that should be kept whole.
+const COMPOUND_EXTENSIONS: [&str; 4] = ["nkit.rvz", "nkit.iso", "nkit.gcz", "tar.gz"];
 
-pub fn save_to_db(conn: &Connection, game: &Game, console: &str) -> Result<()> {
+/// Lowercased extension (no leading dot) of the last path segment of a link or href,
+/// ignoring any query/fragment, e.g. "zip", "7z" or "nkit.rvz".
+pub fn file_extension(link: &str) -> Option<String> {
+    let path = link.split(['?', '#']).next().unwrap_or(link);
+    let last = path.rsplit('/').next()?.to_ascii_lowercase();
+
+    for compound in COMPOUND_EXTENSIONS {
+        if last.ends_with(&format!(".{}", compound)) {
+            return Some(compound.to_string());
+        }
+    }
+
+    let (_, ext) = last.rsplit_once('.')?;
+    if ext.is_empty() || !ext.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return None;
+    }
+
+    Some(ext.to_string())
+}
+
+/// `name` without its archive extension ("Game (USA).zip" -> "Game (USA)").
+pub fn strip_extension(name: &str) -> &str {
+    match file_extension(name) {
+        Some(ext) if name.to_ascii_lowercase().ends_with(&format!(".{}", ext)) => {
+            &name[..name.len() - ext.len() - 1]
+        }
+        _ => name,
+    }
+}
+
+/// Converts a listing size like "1.2 GiB", "512 KiB" or "700 B" into bytes.
+/// Binary units (KiB) use 1024, decimal ones (KB) use 1000. `None` for "-" or junk.
+pub fn parse_size(raw: &str) -> Option<i64> {
+    let raw = raw.trim();
+    let split = raw
+        .find(|c: char| !(c.is_ascii_digit() || c == '.' || c == ','))
+        .unwrap_or(raw.len());
+    let (number, unit) = raw.split_at(split);
+
+    let number: f64 = number.replace(',', "").parse().ok()?;
+
+    let multiplier: f64 = match unit.trim().to_ascii_lowercase().as_str() {
+        "" | "b" | "bytes" => 1.0,
+        "kib" | "k" => 1024.0,
+        "mib" | "m" => 1024.0 * 1024.0,
+        "gib" | "g" => 1024.0 * 1024.0 * 1024.0,
+        "tib" | "t" => 1024.0 * 1024.0 * 1024.0 * 1024.0,
+        "kb" => 1e3,
+        "mb" => 1e6,
+        "gb" => 1e9,
+        "tb" => 1e12,
+        _ => return None,
+    };
+
+    Some((number * multiplier).round() as i64)
+}
+
+const MONTHS: [&str; 12] = ["jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec"];
+
+/// Best-effort unix time (UTC) of a listing's date column, e.g. "07-Dec-2023 01:19",
+/// "2023-Dec-07 01:19" or "2023-12-07 01:19:00". `None` if it doesn't look like a date.
+pub fn parse_listing_date(raw: &str) -> Option<i64> {
+    let mut parts = raw.split_whitespace();
+    let date = parts.next()?;
+    let time = parts.next().unwrap_or("00:00");
+
+    let fields: Vec<&str> = date.split(['-', '/']).collect();
+    let [a, b, c] = fields[..] else {
+        return None;
+    };
+    let month = |m: &str| -> Option<i64> {
+        match m.parse::<i64>() {
+            Ok(n) => Some(n),
+            Err(_) => MONTHS
+                .iter()
+                .position(|name| m.get(..3).is_some_and(|p| p.eq_ignore_ascii_case(name)))
+                .map(|i| i as i64 + 1),
+        }
+    };
+    // Year first ("2023-12-07") or last ("07-Dec-2023")
+    let (year, month, day) = if a.len() == 4 {
+        (a.parse::<i64>().ok()?, month(b)?, c.parse::<i64>().ok()?)
+    } else {
+        (c.parse::<i64>().ok()?, month(b)?, a.parse::<i64>().ok()?)
+    };
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+
+    let mut clock = time.split(':').map(|t| t.parse::<i64>().ok());
+    let hours = clock.next().flatten()?;
+    let minutes = clock.next().flatten().unwrap_or(0);
+    let seconds = clock.next().flatten().unwrap_or(0);
+
+    // Days since 1970-01-01 for a proleptic Gregorian date (Howard Hinnant's days_from_civil)
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let mp = (month + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = era * 146_097 + doe - 719_468;
+
+    Some(days * 86_400 + hours * 3600 + minutes * 60 + seconds)
+}
+
+/// Drops only the games table so a full rebuild keeps console sources and settings.
+pub fn drop_games() -> Result<()> {
+    let conn = db::open()?;
+    snapshot_first_seen(&conn)?;
+    conn.execute("DROP TABLE IF EXISTS games", [])?;
+    // Keyed by game id, which the rebuilt table hands out again; download history and
+    // favorites are matched on (name, console) instead, so they survive
+    conn.execute("DROP TABLE IF EXISTS game_parts", [])?;
+
+    // setup() recreates games at the base schema, so let every migration run again
+    ensure_meta_table(&conn)?;
+    conn.execute("DELETE FROM meta WHERE key = 'schema_version'", [])?;
+
+    info!("Dropped games table");
+    Ok(())
+}
+
+/// Copies each game's `first_seen` into `seen_before`, so the rebuilt rows keep it and
+/// "recently added" doesn't list the whole library. Entries from an earlier, unfinished
+/// rebuild win, being older.
+fn snapshot_first_seen(conn: &Connection) -> Result<()> {
+    migrate_v21_seen_before(conn)?;
+    if !has_column(conn, "games", "first_seen")? {
+        return Ok(());
+    }
     conn.execute(
-        "INSERT INTO games (name, console, date, size, dl_link, is_downloaded) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
-        params![game.name, console, game.date, game.size, game.dl_link, game.is_downloaded],
+        "INSERT OR IGNORE INTO seen_before (name, console, first_seen)
+         SELECT name, console, first_seen FROM games WHERE first_seen IS NOT NULL",
+        [],
     )?;
     Ok(())
 }
 
-pub fn remove_old_db() -> std::io::Result<()> {
-    let file_path = db_path();
-    if file_path.exists() {
-        fs::remove_file(&file_path)?;
-        println!("Removed old DB at {:?}", file_path);
-    } else {
-        println!("No old DB to delete at {:?}...Continuing...", file_path);
+// ------------------------ Scraper (Progress Version) ------------------------
+
+// Console pages fetched at once. The DB writes all stay on the calling thread.
+const SCRAPE_WORKERS: usize = 4;
+
+pub const DEFAULT_SCRAPE_RETRIES: u64 = 2;
+pub const DEFAULT_SCRAPE_RETRY_BACKOFF_MS: u64 = 1000;
+// Gap between listing requests, so a full scrape doesn't hammer a free community mirror
+pub const DEFAULT_SCRAPE_DELAY_MS: u64 = 500;
+
+/// Spaces listing requests at least `delay` apart, across all scrape workers.
+struct Throttle {
+    delay: Duration,
+    next: Mutex<Option<Instant>>,
+}
+
+impl Throttle {
+    fn from_settings() -> std::result::Result<Self, AppError> {
+        let delay_ms = read_u64_setting("scrape_delay_ms", DEFAULT_SCRAPE_DELAY_MS)?;
+        Ok(Throttle {
+            delay: Duration::from_millis(delay_ms),
+            next: Mutex::new(None),
+        })
+    }
+
+    /// Blocks until this caller may send the next request.
+    fn wait(&self) {
+        // Held while sleeping so waiting workers go out one at a time
+        let mut next = self.next.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(at) = *next {
+            let now = Instant::now();
+            if at > now {
+                thread::sleep(at - now);
+            }
+        }
+        *next = Some(Instant::now() + self.delay);
+    }
+}
+
+/// `fetch_console_games` with the configured retries; the delay doubles after each attempt.
+/// Each attempt is bounded by the client's request timeout and goes through `throttle`.
+fn fetch_console_games_with_retries(
+    client: &Client,
+    throttle: &Throttle,
+    site_url: &str,
+    headers: &HeaderMap,
+    retries: u64,
+    backoff_ms: u64,
+) -> std::result::Result<Vec<Game>, AppError> {
+    let mut attempt = 0;
+    loop {
+        match fetch_console_games(client, throttle, site_url, headers) {
+            Ok(games) => return Ok(games),
+            Err(e) if attempt >= retries => {
+                return Err(AppError::network(format!("{} (after {} attempts)", e, attempt + 1)));
+            }
+            Err(e) => {
+                let delay = backoff_ms.saturating_mul(1 << attempt.min(10));
+                warn!("Fetching {} failed: {}; retrying in {} ms", site_url, e, delay);
+                thread::sleep(Duration::from_millis(delay));
+                attempt += 1;
+            }
+        }
+    }
+}
+
+// Upper bound on pages followed for one console, in case "next" links loop
+const MAX_LISTING_PAGES: usize = 50;
+
+// Link texts treated as a "next page" anchor when the page has no rel="next"
+const NEXT_PAGE_LABELS: &[&str] = &["next", "next page", "next »", "next ›"];
+
+/// Absolute URL of the page after `page_url`, if the listing paginates.
+fn next_page_url(document: &Html, page_url: &str) -> Option<String> {
+    let rel_next = Selector::parse("a[rel~=next], link[rel~=next]").ok()?;
+    let anchors = Selector::parse("a").ok()?;
+
+    let href = document
+        .select(&rel_next)
+        .find_map(|e| e.value().attr("href"))
+        .or_else(|| {
+            document
+                .select(&anchors)
+                .find(|a| {
+                    let label = a.text().collect::<String>().trim().to_lowercase();
+                    NEXT_PAGE_LABELS.contains(&label.as_str())
+                })
+                .and_then(|a| a.value().attr("href"))
+        })?;
+
+    reqwest::Url::parse(page_url).ok()?.join(href).ok().map(|u| u.to_string())
+}
+
+/// Fetches one console listing, following "next page" links until they run out,
+/// and parses its rows. Runs on a worker thread, so errors are flattened to
+/// AppError (scraper/reqwest errors aren't all Send). `headers` are the console's
+/// extra request headers, sent with every page.
+fn fetch_console_games(
+    client: &Client,
+    throttle: &Throttle,
+    site_url: &str,
+    headers: &HeaderMap,
+) -> std::result::Result<Vec<Game>, AppError> {
+    let mut games = Vec::new();
+    // Links are still built from `site_url`; apply_mirror rewrites them again at download time
+    let mut page_url = apply_mirror(site_url)?;
+    let mut visited = Vec::new();
+
+    loop {
+        throttle.wait();
+        let (page_games, next) = fetch_listing_page(client, site_url, &page_url, headers)?;
+        games.extend(page_games);
+        visited.push(page_url);
+
+        match next {
+            Some(next) if !visited.contains(&next) => {
+                if visited.len() >= MAX_LISTING_PAGES {
+                    warn!("Stopped following {} after {} pages", site_url, MAX_LISTING_PAGES);
+                    break;
+                }
+                page_url = next;
+            }
+            _ => break,
+        }
+    }
+
+    check_listing_layout(&games)?;
+    Ok(group_split_archives(games))
+}
+
+/// "Game.zip.001" -> ("Game.zip", 1): the archive a numbered part belongs to, and which
+/// part it is. `None` for anything else, including a bare "file.001".
+pub fn split_part_suffix(link: &str) -> Option<(&str, u32)> {
+    let path = link.split(['?', '#']).next().unwrap_or(link);
+    let (base, number) = path.rsplit_once('.')?;
+    if number.len() != 3 || !number.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    file_extension(base)?;
+    Some((base, number.parse().ok()?))
+}
+
+/// Folds the numbered parts of split archives ("X.zip.001", "X.zip.002", …) into one game
+/// per archive: named without the part number, linked to part 1, sized as the sum of the
+/// parts and listing every part in `parts`. Runs over a whole console, since a set's
+/// parts can straddle listing pages.
+fn group_split_archives(games: Vec<Game>) -> Vec<Game> {
+    let mut kept: Vec<(usize, Game)> = Vec::with_capacity(games.len());
+    let mut sets: BTreeMap<String, (usize, Vec<(u32, Game)>)> = BTreeMap::new();
+
+    for (i, game) in games.into_iter().enumerate() {
+        match split_part_suffix(&game.dl_link).map(|(base, n)| (base.to_string(), n)) {
+            Some((base, n)) => sets.entry(base).or_insert_with(|| (i, Vec::new())).1.push((n, game)),
+            None => kept.push((i, game)),
+        }
+    }
+
+    for (base, (position, mut parts)) in sets {
+        parts.sort_by_key(|(n, _)| *n);
+        let links: Vec<String> = parts.iter().map(|(_, g)| g.dl_link.clone()).collect();
+        // Unknown as soon as one part's size is
+        let size_bytes: Option<i64> = parts.iter().map(|(_, g)| g.size_bytes).sum();
+
+        let Some((_, mut game)) = parts.into_iter().next() else {
+            continue;
+        };
+        if let Some((name, _)) = game.name.rsplit_once('.').filter(|(_, n)| n.chars().all(|c| c.is_ascii_digit())) {
+            game.name = name.to_string();
+        }
+        if let Some(bytes) = size_bytes {
+            game.size = format_bytes(bytes.max(0) as u64);
+        }
+        game.size_bytes = size_bytes;
+        game.extension = file_extension(&base);
+        game.parts = links;
+        kept.push((position, game));
+    }
+
+    kept.sort_by_key(|(i, _)| *i);
+    kept.into_iter().map(|(_, game)| game).collect()
+}
+
+/// One page of a listing: its rows plus the next page's URL, if any.
+fn fetch_listing_page(
+    client: &Client,
+    site_url: &str,
+    page_url: &str,
+    headers: &HeaderMap,
+) -> std::result::Result<(Vec<Game>, Option<String>), AppError> {
+    let _permit = connection_permit();
+    let response = client
+        .get(page_url)
+        .headers(headers.clone())
+        .send()
+        .map_err(|e| AppError::network(format!("Request failed: {}", e)))?;
+    if !response.status().is_success() {
+        return Err(AppError::network(format!("HTTP error: {}", response.status())));
+    }
+    let html = response.text().map_err(|e| AppError::network(format!("Read failed: {}", e)))?;
+    let document = Html::parse_document(&html);
+
+    Ok((parse_listing(&document, site_url)?, next_page_url(&document, page_url)))
+}
+
+/// Parses one directory listing page into games, with links built from `site_url`.
+/// No network or DB, so the selectors can be tested against saved HTML.
+pub fn parse_listing(document: &Html, site_url: &str) -> std::result::Result<Vec<Game>, AppError> {
+    let parse = |s: &str| Selector::parse(s).map_err(|e| AppError::internal(format!("Bad selector {}: {}", s, e)));
+    let game_row_selector = parse("tr")?;
+    let name_selector = parse(".link a")?;
+    let date_selector = parse("td:nth-child(3)")?;
+    let size_selector = parse("td:nth-child(2)")?;
+
+    let mut games = Vec::new();
+
+    for row in document.select(&game_row_selector) {
+        // Strip .zip here (not just in remove_bad_data) so upserts match the stored name
+        let name = row
+            .select(&name_selector)
+            .next()
+            .map(|e| e.text().collect::<String>().trim().replace(".zip", ""))
+            .unwrap_or_else(|| "Unknown".to_owned());
+
+        let partial_link = row
+            .select(&name_selector)
+            .next()
+            .and_then(|e| e.value().attr("href"))
+            .map(|url| url.to_owned())
+            .unwrap_or_else(|| "Unknown".to_owned());
+
+        let link = format!("{}{}", site_url, partial_link);
+
+        let date = row
+            .select(&date_selector)
+            .next()
+            .map(|e| e.text().collect::<String>().trim().to_owned())
+            .unwrap_or_else(|| "Unknown".to_owned());
+
+        let size = row
+            .select(&size_selector)
+            .next()
+            .map(|e| e.text().collect::<String>().trim().to_owned())
+            .unwrap_or_else(|| "Unknown".to_owned());
+
+        let size_bytes = parse_size(&size);
+        let extension = file_extension(&partial_link);
+
+        games.push(Game {
+            name,
+            date,
+            size,
+            size_bytes,
+            extension,
+            dl_link: link,
+            is_downloaded: false,
+            parts: Vec::new(),
+        });
+    }
+
+    Ok(games)
+}
+
+// Share of rows the selectors may miss before the page counts as unrecognised
+const MAX_UNKNOWN_RATIO: f64 = 0.9;
+// Below this, a header and parent-dir row alone would trip the ratio
+const MIN_ROWS_FOR_LAYOUT_CHECK: usize = 10;
+
+/// Fails when the selectors clearly no longer match the page. Otherwise every field
+/// comes back "Unknown", remove_bad_data deletes the rows and the console ends up
+/// silently empty.
+fn check_listing_layout(games: &[Game]) -> std::result::Result<(), AppError> {
+    if games.is_empty() {
+        return Err(AppError::network("No table rows found; the page layout likely changed"));
+    }
+    if games.len() < MIN_ROWS_FOR_LAYOUT_CHECK {
+        return Ok(());
+    }
+
+    let unknown = games.iter().filter(|g| g.name == "Unknown").count();
+    if unknown as f64 / games.len() as f64 > MAX_UNKNOWN_RATIO {
+        return Err(AppError::network(format!(
+            "{} of {} rows had no recognisable game link; the page layout likely changed",
+            unknown,
+            games.len()
+        )));
     }
     Ok(())
 }
 
-// ------------------------ Scraper (Progress Version) ------------------------
+// Seeded when scrape_filters is first created; the user can remove any of them
+const DEFAULT_SCRAPE_EXCLUDES: &[&str] = &[
+    r"(?i)\(Demo[^)]*\)",
+    r"(?i)\(Kiosk[^)]*\)",
+    r"(?i)\(Proto[^)]*\)",
+];
+
+/// Name patterns from `scrape_filters`. A game is kept if it matches an include
+/// pattern (or there are none) and no exclude pattern.
+pub struct ScrapeFilters {
+    include: Vec<Regex>,
+    exclude: Vec<Regex>,
+}
+
+impl ScrapeFilters {
+    pub fn load(conn: &Connection) -> Result<Self> {
+        let mut filters = ScrapeFilters { include: Vec::new(), exclude: Vec::new() };
+
+        let mut stmt = conn.prepare("SELECT kind, pattern FROM scrape_filters")?;
+        let rows = stmt
+            .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))?
+            .collect::<Result<Vec<_>>>()?;
+
+        for (kind, pattern) in rows {
+            // add_scrape_filter validates, but don't let one bad row block every scrape
+            let regex = match Regex::new(&pattern) {
+                Ok(regex) => regex,
+                Err(e) => {
+                    warn!("Skipping invalid scrape filter {:?}: {}", pattern, e);
+                    continue;
+                }
+            };
+            match kind.as_str() {
+                "include" => filters.include.push(regex),
+                _ => filters.exclude.push(regex),
+            }
+        }
+        Ok(filters)
+    }
+
+    pub fn allows(&self, name: &str) -> bool {
+        (self.include.is_empty() || self.include.iter().any(|r| r.is_match(name)))
+            && !self.exclude.iter().any(|r| r.is_match(name))
+    }
+}
+
+/// Saves one console's freshly fetched games that pass the scrape filters and runs
+/// the cleanup passes. Returns how many were saved.
+fn write_console_games(conn: &Connection, console_name: &str, games: &[Game], incremental: bool) -> Result<usize> {
+    let filters = ScrapeFilters::load(conn)?;
+    let kept: Vec<&Game> = games.iter().filter(|g| filters.allows(&g.name)).collect();
+    if kept.len() < games.len() {
+        info!("Filtered out {} of {} {} games", games.len() - kept.len(), games.len(), console_name);
+    }
+
+    // A download marking its game done can hold the lock; the batch rolls back and reruns
+    db::retry_busy(|| write_console_batch(conn, console_name, &kept, incremental))?;
+    download::forget_console_size(console_name);
+
+    // The (name, console) index already keeps game rows unique
+    duplicate_cleanup_consoles()?;
+    remove_bad_data()?;
+    Ok(kept.len())
+}
+
+fn write_console_batch(conn: &Connection, console_name: &str, games: &[&Game], incremental: bool) -> Result<()> {
+    let batch = conn.unchecked_transaction()?;
+
+    // Only flag after the page came back, so a failed fetch doesn't hide a whole console
+    if incremental {
+        batch.execute(
+            "UPDATE games SET available = 0 WHERE console = ?1",
+            [console_name],
+        )?;
+    }
+
+    for game in games {
+        save_to_db(&batch, game, console_name)?;
+    }
+    batch.execute(
+        "UPDATE consoles SET last_scraped = ?1 WHERE console = ?2",
+        params![now_unix(), console_name],
+    )?;
+
+    batch.commit()
+}
+
+/// Fetches a console's current listing with the configured retries, without saving it.
+fn fetch_console_listing(conn: &Connection, console_name: &str) -> std::result::Result<Vec<Game>, AppError> {
+    let (site_url, headers): (String, Option<String>) = conn
+        .query_row(
+            "SELECT url, headers FROM consoles WHERE console = ?1",
+            [console_name],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .map_err(|e| AppError::not_found(format!("Unknown console {}: {}", console_name, e)))?;
+
+    let client = scrape_client()?;
+    let retries = read_u64_setting("scrape_retries", DEFAULT_SCRAPE_RETRIES)?;
+    let backoff_ms = read_u64_setting("scrape_retry_backoff_ms", DEFAULT_SCRAPE_RETRY_BACKOFF_MS)?;
+    let throttle = Throttle::from_settings()?;
+    let headers = parse_headers(headers.as_deref())?;
+    fetch_console_games_with_retries(&client, &throttle, &site_url, &headers, retries, backoff_ms)
+}
+
+/// Fetches a console's listing (every page) without touching the DB, e.g. to browse
+/// a source that hasn't been scraped yet.
+pub fn browse_console(console_name: &str) -> std::result::Result<Vec<Game>, AppError> {
+    let conn = db::open().map_err(|e| AppError::db(format!("Failed to open DB: {}", e)))?;
+    fetch_console_listing(&conn, console_name)
+}
+
+/// Re-reads one game's console listing and updates just that game's `dl_link`.
+/// Matches on the exact name first, then on the separator-insensitive one.
+/// Returns the new link.
+pub fn refresh_game_link(game_id: u32) -> std::result::Result<String, AppError> {
+    let conn = db::open().map_err(|e| AppError::db(format!("Failed to open DB: {}", e)))?;
+
+    let (name, console): (String, String) = conn
+        .query_row(
+            "SELECT name, console FROM games WHERE id = ?1",
+            [game_id as i64],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .map_err(|e| AppError::not_found(format!("No game with id {}: {}", game_id, e)))?;
+
+    let games = fetch_console_listing(&conn, &console)?;
+    let wanted = normalize_name(&name);
+    let found = games
+        .iter()
+        .find(|g| g.name == name)
+        .or_else(|| games.iter().find(|g| normalize_name(&g.name) == wanted))
+        .ok_or_else(|| AppError::not_found(format!("{} is no longer listed for {}", name, console)))?;
+
+    db::retry_busy(|| {
+        conn.execute(
+            "UPDATE games SET dl_link = ?1, available = 1 WHERE id = ?2",
+            params![found.dl_link, game_id as i64],
+        )
+    })
+    .map_err(|e| AppError::db(format!("Failed to update link: {}", e)))?;
+
+    Ok(found.dl_link.clone())
+}
+
+/// Incrementally re-scrapes a single console, keeping downloaded flags.
+/// Returns how many games were saved once the scrape filters were applied.
+pub fn scrape_console(console_name: &str) -> std::result::Result<usize, AppError> {
+    let _scraping = maintenance::begin_scrape()?;
+    let conn = db::open().map_err(|e| AppError::db(format!("Failed to open DB: {}", e)))?;
+
+    let games = fetch_console_listing(&conn, console_name)?;
+
+    write_console_games(&conn, console_name, &games, true)
+        .map_err(|e| AppError::db(format!("Failed saving {}: {}", console_name, e)))
+}
+
+/// Outcome of a scrape run; a console that kept failing is skipped, not fatal.
+#[derive(Clone, Debug, Default, serde::Serialize)]
+pub struct ScrapeSummary {
+    pub scraped: usize,
+    pub failed: Vec<(String, AppError)>, // (console, error)
+    pub total_games: usize,
+    pub cancelled: bool, // stopped early by cancel_scrape; what was scraped is kept
+    pub skipped: bool,   // run_startup_tasks found the catalog fresh enough and didn't scrape
+}
+
+// Set by cancel_scrape; scrape_with_progress stops between consoles once it's up
+static SCRAPE_CANCELLED: AtomicBool = AtomicBool::new(false);
+
+/// Asks a running scrape to stop after the consoles already fetched.
+pub fn cancel_scrape() {
+    SCRAPE_CANCELLED.store(true, Ordering::Relaxed);
+}
+
+/// Clears a leftover cancel before a new run starts.
+pub fn reset_scrape_cancel() {
+    SCRAPE_CANCELLED.store(false, Ordering::Relaxed);
+}
+
+pub fn scrape_cancelled() -> bool {
+    SCRAPE_CANCELLED.load(Ordering::Relaxed)
+}
+
+/// One scrape progress update; `console`/`console_games` are set once a console finishes.
+#[derive(Clone, Debug)]
+pub struct ScrapeProgress {
+    pub percent: u8,
+    pub message: String,
+    pub console: Option<String>,
+    pub console_games: Option<usize>,
+    pub total_games: usize,
+}
+
+impl ScrapeProgress {
+    fn status(percent: u8, message: String, total_games: usize) -> Self {
+        ScrapeProgress {
+            percent,
+            message,
+            console: None,
+            console_games: None,
+            total_games,
+        }
+    }
+}
 
 /// Scrape with progress callback.
-/// `on_progress(percent, message)`
+/// `on_progress(ScrapeProgress)`, with per-console counts once a console is saved.
 ///
 /// Percent mapping:
-/// - 0..=29: reserved for caller (remove_old_db/setup/console_fill)
+/// - 0..=29: reserved for caller (drop_games/setup/console_fill)
 /// - 30..=100: scrape progress per console
-pub fn scrape_with_progress<F>(mut on_progress: F) -> Result<(), Box<dyn std::error::Error>>
+///
+/// Pages are fetched by a pool of `SCRAPE_WORKERS` threads and written here as they
+/// arrive, so consoles can finish out of order; the percentage counts completed consoles.
+///
+/// With `incremental` the existing rows are kept: games are upserted by (name, console)
+/// and anything no longer listed for a scraped console is flagged `available = 0`.
+pub fn scrape_with_progress<F>(incremental: bool, mut on_progress: F) -> std::result::Result<ScrapeSummary, Box<dyn std::error::Error>>
 where
-    F: FnMut(u8, String),
+    F: FnMut(ScrapeProgress),
 {
     let db_dir = db_path().parent().unwrap().to_path_buf();
     fs::create_dir_all(&db_dir)?;
 
     // IMPORTANT:
-    // We assume remove_old_db/setup/console_fill already ran before this.
-    let conn = Connection::open(db_path())?;
+    // We assume drop_games/setup/console_fill already ran before this.
+    let conn = db::open()?;
 
-    let console_rows: Vec<(String, String)> = conn
-        .prepare("SELECT console, url FROM consoles ORDER BY id")?
-        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+    let console_rows: Vec<(String, String, Option<String>)> = conn
+        .prepare("SELECT console, url, headers FROM consoles ORDER BY id")?
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?
         .collect::<Result<_, _>>()?;
 
     if console_rows.is_empty() {
-        on_progress(100, "No consoles found; nothing to scrape.".to_string());
-        return Ok(());
+        on_progress(ScrapeProgress::status(100, "No consoles found; nothing to scrape.".to_string(), 0));
+        return Ok(ScrapeSummary::default());
     }
 
     let total = console_rows.len() as u32;
 
-    for (idx, (console_name, site_url)) in console_rows.into_iter().enumerate() {
-        // map each console to progress 30..100
-        let base = 30u32;
-        let span = 70u32;
-
-        let pct_start = base + ((idx as u32 * span) / total);
-        on_progress(pct_start as u8, format!("Scraping: {}", console_name));
-
-        let response = reqwest::blocking::get(&site_url)?;
-        let html = response.text()?;
-        let document = Html::parse_document(&html);
-
-        let game_row_selector = Selector::parse("tr")?;
-        let name_selector = Selector::parse(".link a")?;
-        let date_selector = Selector::parse("td:nth-child(3)")?;
-        let size_selector = Selector::parse("td:nth-child(2)")?;
-
-        for row in document.select(&game_row_selector) {
-            let name = row
-                .select(&name_selector)
-                .next()
-                .map(|e| e.text().collect::<String>().trim().to_owned())
-                .unwrap_or_else(|| "Unknown".to_owned());
-
-            let partial_link = row
-                .select(&name_selector)
-                .next()
-                .and_then(|e| e.value().attr("href"))
-                .map(|url| url.to_owned())
-                .unwrap_or_else(|| "Unknown".to_owned());
-
-            let link = format!("{}{}", site_url, partial_link);
-
-            let date = row
-                .select(&date_selector)
-                .next()
-                .map(|e| e.text().collect::<String>().trim().to_owned())
-                .unwrap_or_else(|| "Unknown".to_owned());
-
-            let size = row
-                .select(&size_selector)
-                .next()
-                .map(|e| e.text().collect::<String>().trim().to_owned())
-                .unwrap_or_else(|| "Unknown".to_owned());
-
-            let game = Game {
-                name,
-                date,
-                size,
-                dl_link: link,
-                is_downloaded: false,
+    // map completed consoles to progress 30..100
+    let base = 30u32;
+    let span = 70u32;
+
+    on_progress(ScrapeProgress::status(base as u8, format!("Scraping {} consoles…", total), 0));
+
+    let client = scrape_client()?;
+    let retries = read_u64_setting("scrape_retries", DEFAULT_SCRAPE_RETRIES)?;
+    let backoff_ms = read_u64_setting("scrape_retry_backoff_ms", DEFAULT_SCRAPE_RETRY_BACKOFF_MS)?;
+    let throttle = Throttle::from_settings()?;
+
+    let jobs = Mutex::new(console_rows.into_iter());
+    let (tx, rx) = mpsc::channel::<(String, std::result::Result<Vec<Game>, AppError>)>();
+
+    let mut summary = thread::scope(|scope| -> std::result::Result<ScrapeSummary, Box<dyn std::error::Error>> {
+        for _ in 0..SCRAPE_WORKERS.min(total as usize) {
+            let tx = tx.clone();
+            let client = &client;
+            let throttle = &throttle;
+            let jobs = &jobs;
+
+            scope.spawn(move || loop {
+                if scrape_cancelled() {
+                    break;
+                }
+                let next = jobs.lock().ok().and_then(|mut j| j.next());
+                let Some((console_name, site_url, headers)) = next else {
+                    break;
+                };
+
+                // A malformed headers column fails just this console
+                let result = parse_headers(headers.as_deref()).and_then(|headers| {
+                    fetch_console_games_with_retries(client, throttle, &site_url, &headers, retries, backoff_ms)
+                });
+                if tx.send((console_name, result)).is_err() {
+                    break;
+                }
+            });
+        }
+        drop(tx);
+
+        let mut done = 0u32;
+        let mut summary = ScrapeSummary::default();
+
+        for (console_name, result) in rx {
+            // Dropping rx on the way out stops the workers at their next send
+            if scrape_cancelled() {
+                break;
+            }
+            done += 1;
+            let pct_done = base + ((done * span) / total);
+
+            let games = match result {
+                Ok(games) => games,
+                Err(e) => {
+                    // Leave this console's rows as they were and carry on with the rest
+                    on_progress(ScrapeProgress {
+                        percent: pct_done.min(99) as u8,
+                        message: format!("Failed: {} ({}/{}): {}", console_name, done, total, e),
+                        console: Some(console_name.clone()),
+                        console_games: None,
+                        total_games: summary.total_games,
+                    });
+                    summary.failed.push((console_name, e));
+                    continue;
+                }
             };
 
-            save_to_db(&conn, &game, &console_name)?;
+            let saved = write_console_games(&conn, &console_name, &games, incremental)?;
+
+            summary.scraped += 1;
+            summary.total_games += saved;
+            on_progress(ScrapeProgress {
+                percent: pct_done.min(99) as u8,
+                message: format!(
+                    "Scraped {}: {} games ({}/{})",
+                    console_name,
+                    saved,
+                    done,
+                    total
+                ),
+                console_games: Some(saved),
+                console: Some(console_name),
+                total_games: summary.total_games,
+            });
         }
 
-        // cleanups (as you already do)
-        duplicate_cleanup_consoles()?;
-        duplicate_cleanup_games()?;
-        remove_bad_data()?;
+        Ok(summary)
+    })?;
 
-        let pct_done = base + (((idx as u32 + 1) * span) / total);
-        on_progress(
-            pct_done.min(99) as u8,
-            format!("Finished: {}", console_name),
-        );
+    // Checked here too: the workers may have stopped before anything reached the loop
+    summary.cancelled = scrape_cancelled() && summary.scraped + summary.failed.len() < total as usize;
+
+    // Every console is back, so the rebuild's snapshot has done its job. Otherwise it's kept
+    // for the consoles that still have to come back.
+    if !incremental && !summary.cancelled && summary.failed.is_empty() {
+        conn.execute("DELETE FROM seen_before", [])?;
     }
 
-    on_progress(100, "All consoles scraped successfully!".to_string());
-    Ok(())
+    let message = if summary.cancelled {
+        format!("Scrape cancelled: {} of {} consoles scraped", summary.scraped, total)
+    } else if summary.failed.is_empty() {
+        "All consoles scraped successfully!".to_string()
+    } else {
+        format!("{} of {} scraped, {} failed", summary.scraped, total, summary.failed.len())
+    };
+    on_progress(ScrapeProgress::status(100, message, summary.total_games));
+    Ok(summary)
 }
 
 // ------------------------ Scraper (Original API, now uses progress) ------------------------
@@ -165,39 +909,41 @@ pub fn scrape() -> Result<(), Box<dyn std::error::Error>> {
     let db_dir = db_path().parent().unwrap().to_path_buf();
     fs::create_dir_all(&db_dir)?;
 
-    remove_old_db()?;
+    drop_games()?;
     setup()?;
     console_fill()?;
 
-    scrape_with_progress(|pct, msg| {
-        println!("[{}%] {}", pct, msg);
+    scrape_with_progress(false, |p| {
+        info!("[{}%] {}", p.percent, p.message);
     })?;
 
-    println!("All consoles scraped successfully!");
+    info!("All consoles scraped successfully!");
     Ok(())
 }
 
 // ------------------------ DB Utilities ------------------------
 
-pub fn duplicate_cleanup_games() -> Result<()> {
-    let conn = Connection::open(db_path())?;
-    conn.execute(
+/// Deletes all but the first row of each (name, console); the same title on two
+/// consoles is two games.
+pub fn duplicate_cleanup_games() -> Result<usize> {
+    let conn = db::open()?;
+    let removed = conn.execute(
         "
         WITH duplicates AS (
           SELECT MIN(rowid) AS keep_id
           FROM games
-          GROUP BY name
+          GROUP BY name, console
         )
         DELETE FROM games
         WHERE rowid NOT IN (SELECT keep_id FROM duplicates)",
         [],
     )?;
-    Ok(())
+    Ok(removed)
 }
 
-pub fn duplicate_cleanup_consoles() -> Result<()> {
-    let conn = Connection::open(db_path())?;
-    conn.execute(
+pub fn duplicate_cleanup_consoles() -> Result<usize> {
+    let conn = db::open()?;
+    let removed = conn.execute(
         "
         WITH duplicates AS (
           SELECT MIN(rowid) AS keep_id
@@ -208,14 +954,16 @@ pub fn duplicate_cleanup_consoles() -> Result<()> {
         WHERE rowid NOT IN (SELECT keep_id FROM duplicates)",
         [],
     )?;
-    Ok(())
+    Ok(removed)
 }
 
-pub fn remove_bad_data() -> Result<()> {
-    let conn = Connection::open(db_path())?;
+/// Returns how many rows were deleted; the `.zip` renames aren't counted.
+pub fn remove_bad_data() -> Result<usize> {
+    let conn = db::open()?;
     let bad_names = ["Unknown", "Parent directory/", "./", "../"];
+    let mut removed = 0;
     for name in bad_names {
-        conn.execute("DELETE FROM games WHERE name = ?1", [name])?;
+        removed += conn.execute("DELETE FROM games WHERE name = ?1", [name])?;
     }
     conn.execute(
         "
@@ -223,7 +971,7 @@ pub fn remove_bad_data() -> Result<()> {
         WHERE name LIKE '%.zip'",
         [],
     )?;
-    Ok(())
+    Ok(removed)
 }
 
 // ------------------------ Consoles Helper ------------------------
@@ -239,7 +987,15 @@ pub fn insert_consoles(conn: &Connection, consoles: &[(&str, &str)]) -> Result<(
 }
 
 pub fn console_fill() -> Result<()> {
-    let conn = Connection::open(db_path())?;
+    let conn = db::open()?;
+
+    // Built-ins are a one-time default; user-added sources live in the same table
+    let existing: i64 = conn.query_row("SELECT COUNT(*) FROM consoles", [], |row| row.get(0))?;
+    if existing > 0 {
+        info!("Consoles already present, skipping");
+        return Ok(());
+    }
+
     let consoles = [
         ("Nintendo New 3DS", "https://myrient.erista.me/files/No-Intro/Nintendo%20-%20New%20Nintendo%203DS%20%28Decrypted%29/"),
         ("Nintendo 3DS", "https://myrient.erista.me/files/No-Intro/Nintendo%20-%20Nintendo%203DS%20%28Decrypted%29/"),
@@ -263,12 +1019,21 @@ pub fn console_fill() -> Result<()> {
         ("Sega Dreamcast", "https://myrient.erista.me/files/No-Intro/Non-Redump%20-%20Sega%20-%20Dreamcast/"),
     ];
     insert_consoles(&conn, &consoles)?;
-    println!("Added Consoles");
+    info!("Added Consoles");
     Ok(())
 }
 
 pub fn setup() -> Result<()> {
-    let conn = Connection::open(db_path())?;
+    let conn = db::open()?;
+    init_schema(&conn)?;
+
+    info!("DB created with Games and Consoles Table Created");
+    Ok(())
+}
+
+/// Creates the base tables and migrates them to the current schema.
+pub(crate) fn init_schema(conn: &Connection) -> Result<()> {
+    // Base schema (version 1); everything newer is added by run_migrations below
 
     // Create consoles table
     conn.execute(
@@ -296,7 +1061,530 @@ pub fn setup() -> Result<()> {
         [],
     )?;
 
-    println!("DB created with Games and Consoles Table Created");
+    run_migrations(conn)
+}
+
+// ------------------------ Migrations ------------------------
+
+type Migration = fn(&Connection) -> Result<()>;
+
+/// Ordered schema steps; entry `i` brings the DB to version `i + 2`.
+/// Steps must be idempotent: a full rebuild recreates `games` at the base schema
+/// and resets the version (see `drop_games`), so they can run again.
+const MIGRATIONS: &[Migration] = &[
+    migrate_v2_size_bytes,
+    migrate_v3_extension,
+    migrate_v4_available,
+    migrate_v5_downloads,
+    migrate_v6_games_fts,
+    migrate_v7_console_dirs,
+    migrate_v8_favorites,
+    migrate_v9_download_path,
+    migrate_v10_clean_name,
+    migrate_v11_scrape_filters,
+    migrate_v12_date_unix,
+    migrate_v13_first_seen,
+    migrate_v14_console_headers,
+    migrate_v15_console_index,
+    migrate_v16_last_scraped,
+    migrate_v17_game_parts,
+    migrate_v18_download_validators,
+    migrate_v19_download_game_keys,
+    migrate_v20_favorites_by_name,
+    migrate_v21_seen_before,
+];
+
+/// Schema version a fully migrated DB is at.
+pub fn latest_schema_version() -> i64 {
+    MIGRATIONS.len() as i64 + 1
+}
+
+fn ensure_meta_table(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS meta (key TEXT PRIMARY KEY, value TEXT NOT NULL)",
+        [],
+    )?;
+    Ok(())
+}
+
+/// Version recorded in `meta`; a DB that predates the table is at the base version 1.
+pub fn schema_version(conn: &Connection) -> Result<i64> {
+    ensure_meta_table(conn)?;
+    let version: Result<String> = conn.query_row(
+        "SELECT value FROM meta WHERE key = 'schema_version'",
+        [],
+        |row| row.get(0),
+    );
+    match version {
+        Ok(v) => Ok(v.parse().unwrap_or(1)),
+        Err(rusqlite::Error::QueryReturnedNoRows) => Ok(1),
+        Err(e) => Err(e),
+    }
+}
+
+fn set_schema_version(conn: &Connection, version: i64) -> Result<()> {
+    conn.execute(
+        "INSERT INTO meta(key, value) VALUES('schema_version', ?1)
+         ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+        [version.to_string()],
+    )?;
+    Ok(())
+}
+
+pub(crate) fn has_column(conn: &Connection, table: &str, column: &str) -> Result<bool> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table))?;
+    let names = stmt
+        .query_map([], |row| row.get::<_, String>(1))?
+        .collect::<Result<Vec<_>>>()?;
+    Ok(names.iter().any(|n| n == column))
+}
+
+fn add_column_if_missing(conn: &Connection, table: &str, column: &str, decl: &str) -> Result<()> {
+    if !has_column(conn, table, column)? {
+        conn.execute(&format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, decl), [])?;
+    }
+    Ok(())
+}
+
+pub fn run_migrations(conn: &Connection) -> Result<()> {
+    let mut version = schema_version(conn)?;
+
+    for (i, migration) in MIGRATIONS.iter().enumerate() {
+        let target = i as i64 + 2;
+        if version >= target {
+            continue;
+        }
+
+        let tx = conn.unchecked_transaction()?;
+        migration(&tx)?;
+        set_schema_version(&tx, target)?;
+        tx.commit()?;
+
+        info!("Migrated DB to schema version {}", target);
+        version = target;
+    }
+
+    Ok(())
+}
+
+fn migrate_v2_size_bytes(conn: &Connection) -> Result<()> {
+    add_column_if_missing(conn, "games", "size_bytes", "INTEGER")
+}
+
+fn migrate_v3_extension(conn: &Connection) -> Result<()> {
+    add_column_if_missing(conn, "games", "extension", "TEXT")
+}
+
+fn migrate_v4_available(conn: &Connection) -> Result<()> {
+    add_column_if_missing(conn, "games", "available", "INTEGER NOT NULL DEFAULT 1")?;
+
+    // Upsert key for incremental scrapes; older DBs may hold duplicates, keep the first
+    conn.execute(
+        "DELETE FROM games WHERE rowid NOT IN (
+            SELECT MIN(rowid) FROM games GROUP BY name, console
+        )",
+        [],
+    )?;
+    conn.execute(
+        "CREATE UNIQUE INDEX IF NOT EXISTS idx_games_name_console ON games (name, console)",
+        [],
+    )?;
+    Ok(())
+}
+
+fn migrate_v5_downloads(conn: &Connection) -> Result<()> {
+    // One row per download attempt; times are unix seconds
+    conn.execute(
+        "
+        CREATE TABLE IF NOT EXISTS downloads (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            game_id INTEGER NOT NULL,
+            state TEXT NOT NULL,
+            started_at INTEGER NOT NULL,
+            finished_at INTEGER,
+            error TEXT,
+            bytes_downloaded INTEGER NOT NULL DEFAULT 0
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+// Separator-insensitive search key; `search_games` normalizes queries the same way.
+const SEARCH_KEY_SQL: &str =
+    "LOWER(REPLACE(REPLACE(REPLACE(REPLACE({}, ' ', ''), '-', ''), '_', ''), ':', ''))";
+
+pub fn search_key(column: &str) -> String {
+    SEARCH_KEY_SQL.replace("{}", column)
+}
+
+fn migrate_v6_games_fts(conn: &Connection) -> Result<()> {
+    // Trigram index over the normalized name, so substring matches stay indexed.
+    // Rebuilt from scratch because a full rebuild drops `games` (and its triggers).
+    conn.execute_batch(&format!(
+        "
+        DROP TABLE IF EXISTS games_fts;
+        CREATE VIRTUAL TABLE games_fts USING fts5(search_key, tokenize = 'trigram');
+
+        INSERT INTO games_fts(rowid, search_key) SELECT id, {key} FROM games;
+
+        CREATE TRIGGER IF NOT EXISTS games_fts_insert AFTER INSERT ON games BEGIN
+            INSERT INTO games_fts(rowid, search_key) VALUES (new.id, {new_key});
+        END;
+        CREATE TRIGGER IF NOT EXISTS games_fts_delete AFTER DELETE ON games BEGIN
+            DELETE FROM games_fts WHERE rowid = old.id;
+        END;
+        CREATE TRIGGER IF NOT EXISTS games_fts_update AFTER UPDATE OF name ON games BEGIN
+            DELETE FROM games_fts WHERE rowid = old.id;
+            INSERT INTO games_fts(rowid, search_key) VALUES (new.id, {new_key});
+        END;
+        ",
+        key = search_key("name"),
+        new_key = search_key("new.name"),
+    ))?;
+    Ok(())
+}
+
+fn migrate_v7_console_dirs(conn: &Connection) -> Result<()> {
+    // Per-console download folder overrides
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS console_dirs (console TEXT PRIMARY KEY, path TEXT NOT NULL)",
+        [],
+    )?;
+    Ok(())
+}
+
+fn migrate_v8_favorites(conn: &Connection) -> Result<()> {
+    // Wishlist; re-keyed by (name, console) in v20
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS favorites (
+            game_id INTEGER PRIMARY KEY,
+            added_at INTEGER NOT NULL
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+fn migrate_v9_download_path(conn: &Connection) -> Result<()> {
+    // Where a finished download ended up: the extract folder, or the archive if not extracted
+    add_column_if_missing(conn, "downloads", "path", "TEXT")
+}
+
+fn migrate_v10_clean_name(conn: &Connection) -> Result<()> {
+    add_column_if_missing(conn, "games", "clean_name", "TEXT")?;
+
+    // Drop the v6 index first so the backfill doesn't churn it row by row
+    conn.execute_batch(
+        "
+        DROP TRIGGER IF EXISTS games_fts_insert;
+        DROP TRIGGER IF EXISTS games_fts_delete;
+        DROP TRIGGER IF EXISTS games_fts_update;
+        DROP TABLE IF EXISTS games_fts;
+        ",
+    )?;
+
+    let rows = {
+        let mut stmt = conn.prepare("SELECT id, name FROM games")?;
+        let rows = stmt
+            .query_map([], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?)))?
+            .collect::<Result<Vec<_>>>()?;
+        rows
+    };
+    for (id, name) in rows {
+        conn.execute(
+            "UPDATE games SET clean_name = ?1 WHERE id = ?2",
+            params![clean_name(&name), id],
+        )?;
+    }
+
+    // Same trigram index as v6, plus the primary title so tag-free matches stay indexed
+    conn.execute_batch(&format!(
+        "
+        CREATE VIRTUAL TABLE games_fts USING fts5(search_key, clean_key, tokenize = 'trigram');
+
+        INSERT INTO games_fts(rowid, search_key, clean_key) SELECT id, {key}, {clean} FROM games;
+
+        CREATE TRIGGER games_fts_insert AFTER INSERT ON games BEGIN
+            INSERT INTO games_fts(rowid, search_key, clean_key) VALUES (new.id, {new_key}, {new_clean});
+        END;
+        CREATE TRIGGER games_fts_delete AFTER DELETE ON games BEGIN
+            DELETE FROM games_fts WHERE rowid = old.id;
+        END;
+        CREATE TRIGGER games_fts_update AFTER UPDATE OF name, clean_name ON games BEGIN
+            DELETE FROM games_fts WHERE rowid = old.id;
+            INSERT INTO games_fts(rowid, search_key, clean_key) VALUES (new.id, {new_key}, {new_clean});
+        END;
+        ",
+        key = search_key("name"),
+        clean = search_key("COALESCE(clean_name, '')"),
+        new_key = search_key("new.name"),
+        new_clean = search_key("COALESCE(new.clean_name, '')"),
+    ))?;
+    Ok(())
+}
+
+fn migrate_v11_scrape_filters(conn: &Connection) -> Result<()> {
+    // Only seed on creation: a rebuild reruns this, and cleared defaults should stay cleared
+    let exists: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = 'scrape_filters'",
+        [],
+        |row| row.get(0),
+    )?;
+
+    // Regexes matched against listing names before they're saved; kind is include or exclude
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS scrape_filters (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            kind TEXT NOT NULL,
+            pattern TEXT NOT NULL,
+            UNIQUE(kind, pattern)
+        )",
+        [],
+    )?;
+
+    if exists == 0 {
+        for pattern in DEFAULT_SCRAPE_EXCLUDES {
+            conn.execute(
+                "INSERT OR IGNORE INTO scrape_filters (kind, pattern) VALUES ('exclude', ?1)",
+                [pattern],
+            )?;
+        }
+    }
+    Ok(())
+}
+
+fn migrate_v12_date_unix(conn: &Connection) -> Result<()> {
+    // Parsed `date`, so listings can be filtered and sorted by when a file was added
+    add_column_if_missing(conn, "games", "date_unix", "INTEGER")?;
+
+    let rows = {
+        let mut stmt = conn.prepare("SELECT id, date FROM games")?;
+        let rows = stmt
+            .query_map([], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?)))?
+            .collect::<Result<Vec<_>>>()?;
+        rows
+    };
+    for (id, date) in rows {
+        conn.execute(
+            "UPDATE games SET date_unix = ?1 WHERE id = ?2",
+            params![parse_listing_date(&date), id],
+        )?;
+    }
+    Ok(())
+}
+
+fn migrate_v13_first_seen(conn: &Connection) -> Result<()> {
+    // When a scrape first inserted the row; upserts leave it alone. Existing rows
+    // count as seen now, so they don't all show up as new after the upgrade.
+    add_column_if_missing(conn, "games", "first_seen", "INTEGER")?;
+    conn.execute(
+        "UPDATE games SET first_seen = ?1 WHERE first_seen IS NULL",
+        [now_unix()],
+    )?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_games_first_seen ON games (first_seen)",
+        [],
+    )?;
+    Ok(())
+}
+
+fn migrate_v14_console_headers(conn: &Connection) -> Result<()> {
+    // Optional JSON object of extra request headers (auth token, referer, ...) per source
+    add_column_if_missing(conn, "consoles", "headers", "TEXT")?;
     Ok(())
 }
 
+fn migrate_v15_console_index(conn: &Connection) -> Result<()> {
+    // Console filters and per-console counts scanned the whole table; name search goes
+    // through games_fts, but exact title lookups use clean_name
+    conn.execute_batch(
+        "
+        CREATE INDEX IF NOT EXISTS idx_games_console ON games (console);
+        CREATE INDEX IF NOT EXISTS idx_games_clean_name ON games (clean_name);
+        ",
+    )?;
+    Ok(())
+}
+
+fn migrate_v16_last_scraped(conn: &Connection) -> Result<()> {
+    // Unix time each console's listing was last saved; NULL until its next scrape
+    add_column_if_missing(conn, "consoles", "last_scraped", "INTEGER")?;
+    Ok(())
+}
+
+fn migrate_v17_game_parts(conn: &Connection) -> Result<()> {
+    // Links of split archives (".zip.001", ".zip.002", …); games.dl_link is part 1
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS game_parts (
+            game_id INTEGER NOT NULL,
+            part INTEGER NOT NULL,
+            url TEXT NOT NULL,
+            PRIMARY KEY (game_id, part)
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+fn migrate_v18_download_validators(conn: &Connection) -> Result<()> {
+    // ETag / Last-Modified the file was served with, to ask the mirror later whether it changed
+    add_column_if_missing(conn, "downloads", "etag", "TEXT")?;
+    add_column_if_missing(conn, "downloads", "last_modified", "TEXT")?;
+    Ok(())
+}
+
+fn migrate_v19_download_game_keys(conn: &Connection) -> Result<()> {
+    // A full rebuild hands the same ids to other games, so history is matched on (name, console).
+    // Rows from before this still point at their original game, unless a rebuild already ran.
+    add_column_if_missing(conn, "downloads", "game_name", "TEXT")?;
+    add_column_if_missing(conn, "downloads", "console", "TEXT")?;
+    conn.execute_batch(
+        "
+        UPDATE downloads SET
+            game_name = (SELECT name FROM games WHERE games.id = downloads.game_id),
+            console = (SELECT console FROM games WHERE games.id = downloads.game_id)
+        WHERE game_name IS NULL;
+        CREATE INDEX IF NOT EXISTS idx_downloads_game ON downloads (console, game_name);
+        ",
+    )?;
+    Ok(())
+}
+
+fn migrate_v20_favorites_by_name(conn: &Connection) -> Result<()> {
+    // Keyed by (name, console) like download history, so a full rebuild can't move a
+    // favorite onto whichever game gets its old id. Prefixed so joins with games stay unambiguous.
+    if has_column(conn, "favorites", "game_name")? {
+        return Ok(());
+    }
+    conn.execute_batch(
+        "
+        CREATE TABLE favorites_by_name (
+            game_name TEXT NOT NULL,
+            game_console TEXT NOT NULL,
+            added_at INTEGER NOT NULL,
+            PRIMARY KEY (game_name, game_console)
+        );
+        INSERT OR IGNORE INTO favorites_by_name (game_name, game_console, added_at)
+            SELECT g.name, g.console, f.added_at FROM favorites f JOIN games g ON g.id = f.game_id;
+        DROP TABLE favorites;
+        ALTER TABLE favorites_by_name RENAME TO favorites;
+        ",
+    )?;
+    Ok(())
+}
+
+fn migrate_v21_seen_before(conn: &Connection) -> Result<()> {
+    // first_seen of the games a full rebuild dropped, until the scrape after it finishes
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS seen_before (
+            name TEXT NOT NULL,
+            console TEXT NOT NULL,
+            first_seen INTEGER NOT NULL,
+            PRIMARY KEY (name, console)
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Saved copy of a Myrient directory page (trimmed to ten games)
+    const MYRIENT_LISTING: &str = include_str!("../tests/fixtures/myrient_gb_listing.html");
+    const BASE_URL: &str = "https://myrient.erista.me/files/No-Intro/Nintendo%20-%20Game%20Boy/";
+
+    fn myrient_games() -> Vec<Game> {
+        parse_listing(&Html::parse_document(MYRIENT_LISTING), BASE_URL).unwrap()
+    }
+
+    #[test]
+    fn parses_myrient_rows() {
+        let games = myrient_games();
+        let tetris = games.iter().find(|g| g.name == "Tetris (World) (Rev 1)").unwrap();
+
+        assert_eq!(tetris.dl_link, format!("{}Tetris%20%28World%29%20%28Rev%201%29.zip", BASE_URL));
+        assert_eq!(tetris.size, "21.5 KiB");
+        assert_eq!(tetris.size_bytes, Some(22_016));
+        assert_eq!(tetris.date, "24-Mar-2024 03:46");
+        assert_eq!(tetris.extension.as_deref(), Some("zip"));
+        // Entities and punctuation survive as text
+        assert!(games.iter().any(|g| g.name == "Legend of Zelda, The - Link's Awakening (USA, Europe) (Rev 2)"));
+    }
+
+    #[test]
+    fn myrient_layout_passes_the_sanity_check() {
+        let games = myrient_games();
+        let real: Vec<&Game> = games
+            .iter()
+            .filter(|g| !["Unknown", "Parent directory/"].contains(&g.name.as_str()))
+            .collect();
+
+        // Header row has no link, the parent row is cleaned up by remove_bad_data later
+        assert_eq!(real.len(), 10);
+        assert!(games.iter().any(|g| g.name == "Unknown"));
+        assert!(check_listing_layout(&games).is_ok());
+        assert_eq!(next_page_url(&Html::parse_document(MYRIENT_LISTING), BASE_URL), None);
+    }
+
+    #[test]
+    fn follows_next_links() {
+        let page = r#"<table></table><a href="?page=2">Next</a>"#;
+        assert_eq!(
+            next_page_url(&Html::parse_document(page), "https://example.org/gb/"),
+            Some("https://example.org/gb/?page=2".to_string())
+        );
+    }
+
+    #[test]
+    fn full_rebuild_keeps_first_seen() {
+        let conn = Connection::open_in_memory().unwrap();
+        init_schema(&conn).unwrap();
+        let game = &myrient_games()[0];
+        save_to_db(&conn, game, "Test").unwrap();
+        conn.execute("UPDATE games SET first_seen = 100", []).unwrap();
+
+        snapshot_first_seen(&conn).unwrap();
+        conn.execute("DELETE FROM games", []).unwrap();
+        save_to_db(&conn, game, "Test").unwrap();
+        let first_seen: i64 = conn
+            .query_row("SELECT first_seen FROM games", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(first_seen, 100);
+    }
+
+    #[test]
+    fn groups_numbered_parts_into_one_game() {
+        let row = |name: &str, href: &str, size: &str| Game {
+            name: name.replace(".zip", ""),
+            date: "01-Jan-2024 00:00".to_string(),
+            size: size.to_string(),
+            size_bytes: parse_size(size),
+            extension: file_extension(href),
+            dl_link: format!("{}{}", BASE_URL, href),
+            is_downloaded: false,
+            parts: Vec::new(),
+        };
+        let games = group_split_archives(vec![
+            row("Big Game (USA).zip.002", "Big%20Game%20(USA).zip.002", "1 KiB"),
+            row("Tetris (World).zip", "Tetris%20(World).zip", "22 KiB"),
+            row("Big Game (USA).zip.001", "Big%20Game%20(USA).zip.001", "2 KiB"),
+        ]);
+
+        let names: Vec<&str> = games.iter().map(|g| g.name.as_str()).collect();
+        assert_eq!(names, ["Big Game (USA)", "Tetris (World)"]);
+
+        let big = &games[0];
+        assert_eq!(big.dl_link, format!("{}Big%20Game%20(USA).zip.001", BASE_URL));
+        assert_eq!(big.parts.len(), 2);
+        assert!(big.parts[1].ends_with(".zip.002"));
+        assert_eq!(big.size_bytes, Some(3 * 1024));
+        assert_eq!(big.extension.as_deref(), Some("zip"));
+        assert!(games[1].parts.is_empty());
+
+        assert_eq!(split_part_suffix("x/Game.7z.010"), Some(("x/Game.7z", 10)));
+        assert_eq!(split_part_suffix("x/track.001"), None);
+    }
+}
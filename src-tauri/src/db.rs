@@ -0,0 +1,47 @@
+use std::thread;
+use std::time::Duration;
+
+use rusqlite::{Connection, ErrorCode, Result};
+use tracing::warn;
+
+use crate::paths::db_path;
+
+// How long a statement waits on another connection's lock before failing with SQLITE_BUSY
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+// Extra attempts `retry_busy` makes once the busy timeout has run out
+const BUSY_RETRIES: u32 = 3;
+const BUSY_RETRY_DELAY: Duration = Duration::from_millis(250);
+
+/// Opens the app DB. Every connection goes through here so they all wait on each
+/// other's locks instead of failing, and use WAL so readers don't block the writer.
+pub fn open() -> Result<Connection> {
+    let conn = Connection::open(db_path())?;
+    conn.busy_timeout(BUSY_TIMEOUT)?;
+    // Persistent once set; later opens just read the mode back
+    conn.pragma_update(None, "journal_mode", "WAL")?;
+    Ok(conn)
+}
+
+pub fn is_busy(e: &rusqlite::Error) -> bool {
+    matches!(
+        e,
+        rusqlite::Error::SqliteFailure(err, _)
+            if matches!(err.code, ErrorCode::DatabaseBusy | ErrorCode::DatabaseLocked)
+    )
+}
+
+/// Runs `op` again (with a growing delay) while it fails with SQLITE_BUSY.
+/// `op` must be safe to repeat, e.g. a single statement or a whole transaction.
+pub fn retry_busy<T>(mut op: impl FnMut() -> Result<T>) -> Result<T> {
+    let mut attempt = 0;
+    loop {
+        match op() {
+            Err(e) if is_busy(&e) && attempt < BUSY_RETRIES => {
+                attempt += 1;
+                warn!("Database busy, retrying ({}/{})", attempt, BUSY_RETRIES);
+                thread::sleep(BUSY_RETRY_DELAY * attempt);
+            }
+            result => return result,
+        }
+    }
+}
@@ -1,16 +1,35 @@
 use std::fs::{self, File, OpenOptions};
 use std::io::{Read, Write, Seek, SeekFrom};
 use std::path::{Path, PathBuf, Component};
+use std::collections::BTreeMap;
 use std::sync::{Arc, atomic::{AtomicU64, Ordering}, Mutex};
 
 use reqwest::blocking::Client;
-use reqwest::header::{ACCEPT_RANGES, CONTENT_LENGTH, RANGE};
+use reqwest::header::{
+    HeaderMap, ACCEPT_RANGES, CONTENT_LENGTH, CONTENT_RANGE, CONTENT_TYPE, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH,
+    LAST_MODIFIED, RANGE,
+};
+use reqwest::StatusCode;
 use rusqlite::Connection;
 use serde::Serialize;
 use tauri::{Emitter, Window};
 use tokio::task;
-
-use crate::query::db_path;
+use tracing::{error, info, warn};
+
+use crate::checksum::{compute_hash, HashAlgorithm};
+use crate::data::{self, file_extension};
+use crate::error::AppError;
+use crate::history::{self, DownloadState};
+use crate::library;
+use crate::maintenance;
+use crate::net::{
+    apply_mirror, build_client, connection_permit, connection_permit_unless, source_client, ConnectionPermit,
+};
+use crate::db;
+use crate::paths::default_download_dir;
+use crate::query::get_game;
+use crate::queue;
+use crate::settings::{ensure_writable_dir, read_bool_setting, read_setting, read_u64_setting};
 
 #[derive(Serialize, Clone, Debug)]
 struct DownloadProgressPayload {
@@ -23,67 +42,278 @@ struct DownloadCompletePayload {
     id: u32,
 }
 
-fn ensure_settings_table(conn: &Connection) -> Result<(), String> {
+#[derive(Serialize, Clone, Debug)]
+struct DownloadCancelledPayload {
+    id: u32,
+}
+
+#[derive(Serialize, Clone, Debug)]
+struct DownloadLinkStalePayload {
+    id: u32,
+    url: String,
+    refreshed: bool,
+}
+
+#[derive(Serialize, Clone, Debug)]
+struct DownloadRetryingPayload {
+    id: u32,
+    attempt: u32,
+    max_attempts: u32,
+    delay_ms: u64,
+    error: String,
+}
+
+#[derive(Serialize, Clone, Debug)]
+struct ExtractProgressPayload {
+    id: u32,
+    index: usize,
+    total: usize,
+    bytes_extracted: u64,
+    entry_size: u64,
+}
+
+#[derive(Serialize, Clone, Debug)]
+struct BatchProgressPayload {
+    total: usize,
+    completed: usize,
+    in_progress: usize,
+    overall_bytes: u64,
+    overall_total: u64, // only counts downloads whose size is known so far
+}
+
+// Emit byte-level extract progress at most once per this many bytes.
+const EXTRACT_EMIT_EVERY: u64 = 1024 * 1024;
+
+/// Reader wrapper that reports the running byte count to `on_progress`.
+struct ProgressReader<R, F: FnMut(u64)> {
+    inner: R,
+    done: u64,
+    last_emit: u64,
+    on_progress: F,
+}
+
+impl<R: Read, F: FnMut(u64)> ProgressReader<R, F> {
+    fn new(inner: R, on_progress: F) -> Self {
+        Self {
+            inner,
+            done: 0,
+            last_emit: 0,
+            on_progress,
+        }
+    }
+}
+
+impl<R: Read, F: FnMut(u64)> Read for ProgressReader<R, F> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.done += n as u64;
+
+        if n == 0 || self.done - self.last_emit >= EXTRACT_EMIT_EVERY {
+            self.last_emit = self.done;
+            (self.on_progress)(self.done);
+        }
+
+        Ok(n)
+    }
+}
+
+fn ensure_settings_table(conn: &Connection) -> Result<(), AppError> {
     conn.execute(
         "CREATE TABLE IF NOT EXISTS settings (key TEXT PRIMARY KEY, value TEXT NOT NULL)",
         [],
     )
-    .map_err(|e| format!("Failed creating settings table: {}", e))?;
+    .map_err(|e| AppError::db(format!("Failed creating settings table: {}", e)))?;
     Ok(())
 }
 
-fn default_download_dir() -> Result<PathBuf, String> {
-    let mut p = dirs::home_dir().ok_or("...")?;
-    p.push(".rusty-roms");
-    p.push("downloads");
-    Ok(p)
-}
-
 /// Resolves the download directory:
 /// - if `override_dir` is Some, use that
+/// - else the console's directory from `console_dirs`
 /// - else read from settings table
 /// - else fallback to default_download_dir()
-fn resolve_download_dir(override_dir: Option<String>) -> Result<PathBuf, String> {
+pub(crate) fn resolve_download_dir(override_dir: Option<String>, console: Option<&str>) -> Result<PathBuf, AppError> {
     if let Some(p) = override_dir {
         if p.trim().is_empty() {
-            return Err("downloadDir cannot be empty".into());
+            return Err(AppError::invalid_input("downloadDir cannot be empty"));
         }
         return Ok(PathBuf::from(p));
     }
 
-    let conn = Connection::open(db_path())
-        .map_err(|e| format!("Failed to open DB: {}", e))?;
-    ensure_settings_table(&conn)?;
+    let conn = db::open()
+        .map_err(|e| AppError::db(format!("Failed to open DB: {}", e)))?;
 
-    let saved: Result<String, _> = conn.query_row(
-        "SELECT value FROM settings WHERE key='download_dir'",
-        [],
-        |row| row.get(0),
-    );
+    if let Some(dir) = console.and_then(|c| console_dir(&conn, c)) {
+        return Ok(dir);
+    }
+
+    ensure_settings_table(&conn)?;
 
-    match saved {
-        Ok(v) if !v.trim().is_empty() => Ok(PathBuf::from(v)),
-        _ => default_download_dir(),
+    // library_dir is the home for finished downloads when downloads are staged elsewhere
+    for key in ["library_dir", "download_dir"] {
+        let saved: Result<String, _> = conn.query_row(
+            "SELECT value FROM settings WHERE key = ?1",
+            [key],
+            |row| row.get(0),
+        );
+        if let Ok(v) = saved {
+            if !v.trim().is_empty() {
+                return Ok(PathBuf::from(v));
+            }
+        }
     }
-}
 
-fn mark_downloaded(id: u32) -> Result<(), String> {
-    let conn = Connection::open(db_path())
-        .map_err(|e| format!("Failed to open DB: {}", e))?;
+    default_download_dir()
+}
 
-    conn.execute(
-        "UPDATE games SET is_downloaded = 1 WHERE id = ?1",
-        [id as i64],
+/// The console's own folder from `console_dirs`, if it has one.
+fn console_dir(conn: &Connection, console: &str) -> Option<PathBuf> {
+    conn.query_row(
+        "SELECT path FROM console_dirs WHERE console = ?1",
+        [console],
+        |row| row.get::<_, String>(0),
     )
-    .map_err(|e| format!("Failed to update is_downloaded: {}", e))?;
+    .ok()
+    .filter(|v| !v.trim().is_empty())
+    .map(PathBuf::from)
+}
+
+/// Where a download's archive and its extracted files go, as (zip root, extract root).
+/// Each is its per-call override if given; else the download dir when one was passed or
+/// the console has its own folder; else the `zip_dir` / `extract_dir` setting; else the
+/// download dir. Both are the download dir unless something splits them.
+pub(crate) fn resolve_download_targets(
+    download_dir: Option<String>,
+    zip_dir: Option<String>,
+    extract_dir: Option<String>,
+    console: Option<&str>,
+) -> Result<(PathBuf, PathBuf), AppError> {
+    let pinned = download_dir.is_some() || {
+        let conn = db::open().map_err(|e| AppError::db(format!("Failed to open DB: {}", e)))?;
+        console.and_then(|c| console_dir(&conn, c)).is_some()
+    };
+    let downloads_dir = resolve_download_dir(download_dir, console)?;
+
+    let resolve = |override_dir: Option<String>, key: &str| -> Result<PathBuf, AppError> {
+        if let Some(p) = override_dir {
+            if p.trim().is_empty() {
+                return Err(AppError::invalid_input(format!("{} cannot be empty", key)));
+            }
+            return Ok(PathBuf::from(p));
+        }
+        if pinned {
+            return Ok(downloads_dir.clone());
+        }
+        Ok(read_setting(key)?
+            .filter(|dir| !dir.trim().is_empty())
+            .map(PathBuf::from)
+            .unwrap_or_else(|| downloads_dir.clone()))
+    };
+
+    Ok((resolve(zip_dir, "zip_dir")?, resolve(extract_dir, "extract_dir")?))
+}
+
+fn mark_downloaded(id: u32) -> Result<(), AppError> {
+    let conn = db::open()
+        .map_err(|e| AppError::db(format!("Failed to open DB: {}", e)))?;
+
+    db::retry_busy(|| {
+        conn.execute(
+            "UPDATE games SET is_downloaded = 1 WHERE id = ?1",
+            [id as i64],
+        )
+    })
+    .map_err(|e| AppError::db(format!("Failed to update is_downloaded: {}", e)))?;
+
+    Ok(())
+}
+
+fn unmark_downloaded(id: u32) -> Result<(), AppError> {
+    let conn = db::open()
+        .map_err(|e| AppError::db(format!("Failed to open DB: {}", e)))?;
+
+    db::retry_busy(|| {
+        conn.execute(
+            "UPDATE games SET is_downloaded = 0 WHERE id = ?1",
+            [id as i64],
+        )
+    })
+    .map_err(|e| AppError::db(format!("Failed to update is_downloaded: {}", e)))?;
 
     Ok(())
 }
 
-fn emit_progress(window: &Window, id: u32, msg: String) -> Result<(), String> {
+/// Marks the game as downloaded and notifies the UI.
+fn finish_download(window: &Window, id: u32) -> Result<(), AppError> {
+    mark_downloaded(id)?;
+
+    window
+        .emit("download-complete", DownloadCompletePayload { id })
+        .map_err(|e| AppError::internal(format!("Emit failed: {}", e)))
+}
+
+fn emit_progress(window: &Window, id: u32, msg: String) -> Result<(), AppError> {
     window
         .emit("download-progress", DownloadProgressPayload { id, progress: msg })
-        .map_err(|e| format!("Emit failed: {}", e))
+        .map_err(|e| AppError::internal(format!("Emit failed: {}", e)))
+}
+
+fn emit_extract_progress(
+    window: &Window,
+    id: u32,
+    index: usize,
+    total: usize,
+    bytes_extracted: u64,
+    entry_size: u64,
+) -> Result<(), AppError> {
+    window
+        .emit(
+            "extract-progress",
+            ExtractProgressPayload {
+                id,
+                index,
+                total,
+                bytes_extracted,
+                entry_size,
+            },
+        )
+        .map_err(|e| AppError::internal(format!("Emit failed: {}", e)))
+}
+
+/// A connection permit for download `id`; fails if it's cancelled while waiting for one.
+fn download_permit(id: u32) -> Result<ConnectionPermit, AppError> {
+    connection_permit_unless(|| queue::is_cancelled(id)).ok_or_else(|| AppError::cancelled("Download cancelled"))
+}
+
+/// Between reads: waits out a queue pause, then bails if the download was cancelled.
+/// The connection permit is given up while paused so other requests can have it.
+fn pause_point(id: u32, permit: &mut Option<ConnectionPermit>) -> Result<(), AppError> {
+    let state = queue::pause_state();
+    if state.paused && state.pause_running {
+        permit.take();
+        queue::wait_while_paused(id);
+        check_cancelled(id)?;
+        *permit = Some(download_permit(id)?);
+    }
+    check_cancelled(id)
+}
+
+fn check_cancelled(id: u32) -> Result<(), AppError> {
+    if queue::is_cancelled(id) {
+        return Err(AppError::cancelled("Download cancelled"));
+    }
+    Ok(())
+}
+
+// `download_buffer_kb`: bytes read from the socket per call, in KiB
+pub const DEFAULT_DOWNLOAD_BUFFER_KB: u64 = 32;
+pub const MIN_DOWNLOAD_BUFFER_KB: u64 = 4;
+pub const MAX_DOWNLOAD_BUFFER_KB: u64 = 1024;
+
+/// Read buffer for the download loops, from `download_buffer_kb`.
+fn download_buffer() -> Result<Vec<u8>, AppError> {
+    let kb = read_u64_setting("download_buffer_kb", DEFAULT_DOWNLOAD_BUFFER_KB)?
+        .clamp(MIN_DOWNLOAD_BUFFER_KB, MAX_DOWNLOAD_BUFFER_KB);
+    Ok(vec![0u8; kb as usize * 1024])
 }
 
 fn single_stream_download(
@@ -92,14 +322,18 @@ fn single_stream_download(
     id: u32,
     url: &str,
     file_path: &Path,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
+    let mut permit = Some(download_permit(id)?);
     let mut response = client
         .get(url)
         .send()
-        .map_err(|e| format!("Request failed: {}", e))?;
+        .map_err(|e| AppError::network(format!("Request failed: {}", e)))?;
 
+    if is_stale_status(response.status()) {
+        return Err(stale_link_error(response.status()));
+    }
     if !response.status().is_success() {
-        return Err(format!("HTTP error: {}", response.status()));
+        return Err(AppError::network(format!("HTTP error: {}", response.status())));
     }
 
     let total_size: u64 = response
@@ -109,25 +343,27 @@ fn single_stream_download(
         .and_then(|v| v.parse::<u64>().ok())
         .unwrap_or(0);
 
-    let mut file = File::create(file_path).map_err(|e| format!("File create error: {}", e))?;
+    let mut file = File::create(file_path).map_err(|e| AppError::io(format!("File create error: {}", e)))?;
 
     let mut downloaded: u64 = 0;
-    let mut buffer = [0u8; 8192];
+    let mut buffer = download_buffer()?;
 
     if total_size == 0 {
         emit_progress(window, id, "Downloading…".to_string())?;
     }
 
     loop {
-        let bytes_read = response.read(&mut buffer).map_err(|e| format!("Read error: {}", e))?;
+        pause_point(id, &mut permit)?;
+        let bytes_read = response.read(&mut buffer).map_err(|e| AppError::network(format!("Read error: {}", e)))?;
         if bytes_read == 0 {
             break;
         }
 
         file.write_all(&buffer[..bytes_read])
-            .map_err(|e| format!("Write error: {}", e))?;
+            .map_err(|e| AppError::io(format!("Write error: {}", e)))?;
 
         downloaded = downloaded.saturating_add(bytes_read as u64);
+        queue::set_bytes(id, downloaded, total_size);
 
         if total_size > 0 {
             let percent = (downloaded as f64 / total_size as f64) * 100.0;
@@ -142,33 +378,473 @@ fn single_stream_download(
     Ok(())
 }
 
-fn ranged_parallel_download_4(
-    client: &Client,
-    window: &Window,
-    id: u32,
-    url: &str,
-    file_path: &Path,
-) -> Result<(), String> {
-    let head = client.head(url).send().map_err(|e| format!("HEAD failed: {}", e))?;
+// Values of the `download_mode` setting; parallel is the default
+pub const DOWNLOAD_MODE_PARALLEL: &str = "parallel";
+pub const DOWNLOAD_MODE_SINGLE: &str = "single";
+
+/// What a HEAD request tells us about a remote file.
+struct RemoteInfo {
+    total_size: u64, // 0 when the server doesn't say
+    accepts_ranges: bool,
+    etag: Option<String>,
+    last_modified: Option<String>,
+}
+
+/// 404/410 mean the file moved or was removed, so retrying the same link won't help.
+fn is_stale_status(status: StatusCode) -> bool {
+    status == StatusCode::NOT_FOUND || status == StatusCode::GONE
+}
+
+fn stale_link_error(status: StatusCode) -> AppError {
+    AppError::stale(format!(
+        "Download link is stale (HTTP {}); refresh the library to update it",
+        status
+    ))
+}
+
+fn probe_remote(client: &Client, url: &str) -> Result<RemoteInfo, AppError> {
+    // Redirects were already followed by the client (up to MAX_REDIRECTS)
+    let _permit = connection_permit();
+    let head = client.head(url).send().map_err(|e| AppError::network(format!("HEAD failed: {}", e)))?;
+    if is_stale_status(head.status()) {
+        return Err(stale_link_error(head.status()));
+    }
     if !head.status().is_success() {
-        return Err(format!("HEAD HTTP error: {}", head.status()));
+        return Err(AppError::network(format!("HEAD HTTP error: {}", head.status())));
     }
 
-    let total_size: u64 = head
-        .headers()
+    Ok(remote_info(head.headers()))
+}
+
+fn remote_info(headers: &HeaderMap) -> RemoteInfo {
+    let total_size: u64 = headers
         .get(CONTENT_LENGTH)
         .and_then(|v| v.to_str().ok())
         .and_then(|v| v.parse::<u64>().ok())
         .unwrap_or(0);
 
-    let accept_ranges = head
-        .headers()
+    let accept_ranges = headers
         .get(ACCEPT_RANGES)
         .and_then(|v| v.to_str().ok())
         .unwrap_or("")
         .to_ascii_lowercase();
 
-    if total_size == 0 || !accept_ranges.contains("bytes") {
+    RemoteInfo {
+        total_size,
+        accepts_ranges: accept_ranges.contains("bytes"),
+        etag: header_string(headers, ETAG),
+        last_modified: header_string(headers, LAST_MODIFIED),
+    }
+}
+
+fn header_string(headers: &HeaderMap, name: reqwest::header::HeaderName) -> Option<String> {
+    headers
+        .get(name)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
+/// Total from a `Content-Range` value like "bytes 0-0/12345"; `None` for "*" or junk.
+fn content_range_total(value: &str) -> Option<u64> {
+    let (unit, range) = value.trim().split_once(' ')?;
+    if !unit.eq_ignore_ascii_case("bytes") {
+        return None;
+    }
+    range.rsplit_once('/')?.1.trim().parse().ok()
+}
+
+/// For servers that hide Content-Length on HEAD: asks for the last byte and reads the
+/// total from `Content-Range`. A 206 also proves ranges work, whatever Accept-Ranges said.
+fn probe_range_total(client: &Client, url: &str) -> Option<u64> {
+    let _permit = connection_permit();
+    let resp = client.get(url).header(RANGE, "bytes=-1").send().ok()?;
+    if resp.status() != StatusCode::PARTIAL_CONTENT {
+        // Most likely the whole file as a 200; dropping the response stops reading it
+        return None;
+    }
+
+    resp.headers()
+        .get(CONTENT_RANGE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(content_range_total)
+        .filter(|total| *total > 0)
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct LinkInfo {
+    pub status: u16,
+    pub final_url: String, // after redirects
+    pub content_length: Option<u64>,
+    pub content_type: Option<String>,
+    pub accepts_ranges: bool,
+    pub stale: bool,
+}
+
+/// HEADs a URL and reports what a download would get, without fetching the body.
+/// Non-2xx statuses are reported rather than returned as errors.
+#[tauri::command]
+pub async fn validate_link(url: String) -> Result<LinkInfo, AppError> {
+    let validate_task = task::spawn_blocking(move || -> Result<LinkInfo, AppError> {
+        let client = build_client()?;
+        let url = apply_mirror(&url)?;
+        let _permit = connection_permit();
+        let head = client.head(&url).send().map_err(|e| AppError::network(format!("HEAD failed: {}", e)))?;
+
+        let info = remote_info(head.headers());
+        let content_type = head
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .map(|v| v.to_string());
+
+        Ok(LinkInfo {
+            status: head.status().as_u16(),
+            final_url: head.url().to_string(),
+            content_length: (info.total_size > 0).then_some(info.total_size),
+            content_type,
+            accepts_ranges: info.accepts_ranges,
+            stale: is_stale_status(head.status()),
+        })
+    });
+
+    validate_task.await.map_err(|e| AppError::internal(e.to_string()))?
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct ArchiveEntry {
+    pub name: String,
+    pub size: u64, // uncompressed
+    pub compressed_size: u64,
+    pub is_dir: bool,
+}
+
+const EOCD_SIG: u64 = 0x0605_4b50;
+const ZIP64_LOCATOR_SIG: u64 = 0x0706_4b50;
+const ZIP64_EOCD_SIG: u64 = 0x0606_4b50;
+const CENTRAL_HEADER_SIG: u64 = 0x0201_4b50;
+const CENTRAL_HEADER_LEN: usize = 46;
+const ZIP64_EXTRA_ID: u64 = 0x0001;
+
+// End-of-central-directory record with the longest comment it can carry, plus the
+// ZIP64 record and locator that sit right before it
+const ZIP_TAIL_LEN: u64 = 22 + u16::MAX as u64 + 20 + 56;
+// Sanity cap on the directory itself; even huge sets stay well under this
+const MAX_CENTRAL_DIRECTORY_LEN: u64 = 64 * 1024 * 1024;
+
+fn le_u16(bytes: &[u8], at: usize) -> Option<u64> {
+    Some(u16::from_le_bytes(bytes.get(at..at + 2)?.try_into().ok()?) as u64)
+}
+
+fn le_u32(bytes: &[u8], at: usize) -> Option<u64> {
+    Some(u32::from_le_bytes(bytes.get(at..at + 4)?.try_into().ok()?) as u64)
+}
+
+fn le_u64(bytes: &[u8], at: usize) -> Option<u64> {
+    Some(u64::from_le_bytes(bytes.get(at..at + 8)?.try_into().ok()?))
+}
+
+/// (offset, size) of a zip's central directory, read from `tail`: the last bytes of an
+/// archive `total` bytes long.
+fn central_directory_location(tail: &[u8], total: u64) -> Result<(u64, u64), AppError> {
+    let not_zip = || AppError::io("Not a zip archive (no end of central directory record)");
+    let tail_start = total.saturating_sub(tail.len() as u64);
+
+    // Scan backwards: the record is at the very end unless the archive has a comment
+    let eocd = (0..tail.len().saturating_sub(21))
+        .rev()
+        .find(|&i| le_u32(tail, i) == Some(EOCD_SIG))
+        .ok_or_else(not_zip)?;
+    let size = le_u32(tail, eocd + 12).ok_or_else(not_zip)?;
+    let offset = le_u32(tail, eocd + 16).ok_or_else(not_zip)?;
+    if size != u32::MAX as u64 && offset != u32::MAX as u64 {
+        return Ok((offset, size));
+    }
+
+    // ZIP64: the locator before the record points at a ZIP64 record with the real values
+    let bad_zip64 = || AppError::io("ZIP64 archive with a missing or unreadable ZIP64 record");
+    let locator = eocd
+        .checked_sub(20)
+        .filter(|&i| le_u32(tail, i) == Some(ZIP64_LOCATOR_SIG))
+        .ok_or_else(bad_zip64)?;
+    let record = le_u64(tail, locator + 8)
+        .and_then(|at| at.checked_sub(tail_start))
+        .map(|at| at as usize)
+        .filter(|&at| le_u32(tail, at) == Some(ZIP64_EOCD_SIG))
+        .ok_or_else(bad_zip64)?;
+
+    Ok((
+        le_u64(tail, record + 48).ok_or_else(bad_zip64)?,
+        le_u64(tail, record + 40).ok_or_else(bad_zip64)?,
+    ))
+}
+
+/// Entries listed in a zip's central directory, in archive order.
+fn parse_central_directory(dir: &[u8]) -> Result<Vec<ArchiveEntry>, AppError> {
+    let truncated = || AppError::io("Zip central directory is truncated");
+    let mut entries = Vec::new();
+    let mut at = 0;
+
+    while le_u32(dir, at) == Some(CENTRAL_HEADER_SIG) {
+        let mut compressed_size = le_u32(dir, at + 20).ok_or_else(truncated)?;
+        let mut size = le_u32(dir, at + 24).ok_or_else(truncated)?;
+        let name_len = le_u16(dir, at + 28).ok_or_else(truncated)? as usize;
+        let extra_len = le_u16(dir, at + 30).ok_or_else(truncated)? as usize;
+        let comment_len = le_u16(dir, at + 32).ok_or_else(truncated)? as usize;
+
+        let name_at = at + CENTRAL_HEADER_LEN;
+        let name = dir.get(name_at..name_at + name_len).ok_or_else(truncated)?;
+        let name = String::from_utf8_lossy(name).to_string();
+        let extra = dir
+            .get(name_at + name_len..name_at + name_len + extra_len)
+            .ok_or_else(truncated)?;
+
+        // Sizes too big for 32 bits are 0xFFFFFFFF here and live in the ZIP64 extra
+        // field instead, uncompressed first
+        let mut field = 0;
+        while let (Some(id), Some(len)) = (le_u16(extra, field), le_u16(extra, field + 2)) {
+            let data = extra.get(field + 4..field + 4 + len as usize).unwrap_or(&[]);
+            if id == ZIP64_EXTRA_ID {
+                let mut next = 0;
+                if size == u32::MAX as u64 {
+                    size = le_u64(data, next).ok_or_else(truncated)?;
+                    next += 8;
+                }
+                if compressed_size == u32::MAX as u64 {
+                    compressed_size = le_u64(data, next).ok_or_else(truncated)?;
+                }
+                break;
+            }
+            field += 4 + len as usize;
+        }
+
+        entries.push(ArchiveEntry {
+            is_dir: name.ends_with('/'),
+            name,
+            size,
+            compressed_size,
+        });
+        at = name_at + name_len + extra_len + comment_len;
+    }
+
+    Ok(entries)
+}
+
+/// Bytes `start..=end` of `url`. Fails unless the server answers with just that range.
+fn read_range(client: &Client, url: &str, start: u64, end: u64) -> Result<Vec<u8>, AppError> {
+    let _permit = connection_permit();
+    let resp = client
+        .get(url)
+        .header(RANGE, format!("bytes={}-{}", start, end))
+        .send()
+        .map_err(|e| AppError::network(format!("Range request failed: {}", e)))?;
+    if resp.status() != StatusCode::PARTIAL_CONTENT {
+        // Most likely the whole file as a 200; dropping the response stops reading it
+        return Err(AppError::network(format!(
+            "Server doesn't support range requests (HTTP {})",
+            resp.status()
+        )));
+    }
+
+    resp.bytes()
+        .map(|b| b.to_vec())
+        .map_err(|e| AppError::network(format!("Read failed: {}", e)))
+}
+
+/// Lists a game's zip without downloading it: fetches just the tail with the end of
+/// central directory record, then the central directory itself, with range requests.
+#[tauri::command]
+pub async fn list_archive_contents(id: u32) -> Result<Vec<ArchiveEntry>, AppError> {
+    let list_task = task::spawn_blocking(move || -> Result<Vec<ArchiveEntry>, AppError> {
+        let game = get_game(id as i64)?.ok_or_else(|| AppError::not_found(format!("No game with id {}", id)))?;
+        if file_extension(&game.dl_link).as_deref() != Some("zip") {
+            return Err(AppError::invalid_input(format!("{} is not a zip archive", game.name)));
+        }
+
+        let client = source_client(Some(&game.console))?;
+        let url = apply_mirror(&game.dl_link)?;
+        let mut total = probe_remote(&client, &url)?.total_size;
+        if total == 0 {
+            total = probe_range_total(&client, &url)
+                .ok_or_else(|| AppError::network("Server doesn't report the archive's size"))?;
+        }
+
+        let tail_start = total.saturating_sub(ZIP_TAIL_LEN);
+        let tail = read_range(&client, &url, tail_start, total - 1)?;
+        let (offset, size) = central_directory_location(&tail, total)?;
+        if size > MAX_CENTRAL_DIRECTORY_LEN || offset.saturating_add(size) > total {
+            return Err(AppError::io("Zip central directory is out of bounds"));
+        }
+
+        // Small archives usually have the whole directory in the tail already
+        if offset >= tail_start {
+            let start = (offset - tail_start) as usize;
+            return parse_central_directory(&tail[start..start + size as usize]);
+        }
+        if size == 0 {
+            return Ok(Vec::new());
+        }
+        parse_central_directory(&read_range(&client, &url, offset, offset + size - 1)?)
+    });
+
+    list_task.await.map_err(|e| AppError::internal(e.to_string()))?
+}
+
+pub(crate) fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[0])
+    } else {
+        format!("{:.2} {}", value, UNITS[unit])
+    }
+}
+
+/// Fails early when the volume holding `dir` can't fit `needed` bytes.
+fn ensure_free_space(dir: &Path, needed: u64) -> Result<(), AppError> {
+    let available = fs2::available_space(dir)
+        .map_err(|e| AppError::io(format!("Failed to query free space for {:?}: {}", dir, e)))?;
+
+    if available < needed {
+        return Err(AppError::io(format!(
+            "Not enough free space: need {}, have {}",
+            format_bytes(needed),
+            format_bytes(available)
+        )));
+    }
+
+    Ok(())
+}
+
+const CHUNK_MAX_RETRIES: u32 = 3;
+const CHUNK_RETRY_BASE_MS: u64 = 1000;
+
+enum ChunkError {
+    /// Dropped connection, timeout or 5xx; worth re-requesting.
+    Transient(AppError),
+    Fatal(AppError),
+}
+
+/// Downloads `offset..=end` into the shared file, advancing `offset` as bytes land
+/// so a retry can resume from the right place.
+fn fetch_range(
+    client: &Client,
+    id: u32,
+    url: &str,
+    offset: &mut u64,
+    end: u64,
+    file: &Mutex<File>,
+    downloaded: &AtomicU64,
+) -> Result<(), ChunkError> {
+    if *offset > end {
+        return Ok(());
+    }
+
+    let range_value = format!("bytes={}-{}", offset, end);
+    let mut permit = Some(download_permit(id).map_err(ChunkError::Fatal)?);
+
+    let mut resp = client
+        .get(url)
+        .header(RANGE, range_value)
+        .send()
+        .map_err(|e| ChunkError::Transient(AppError::network(format!("Range request failed: {}", e))))?;
+
+    let status = resp.status();
+    if !(status.as_u16() == 206 || status.is_success()) {
+        let msg = AppError::network(format!("Range HTTP error: {}", status));
+        if status.is_server_error() || status.as_u16() == 429 {
+            return Err(ChunkError::Transient(msg));
+        }
+        return Err(ChunkError::Fatal(msg));
+    }
+
+    let mut buffer = download_buffer().map_err(ChunkError::Fatal)?;
+
+    loop {
+        pause_point(id, &mut permit).map_err(ChunkError::Fatal)?;
+        let n = resp
+            .read(&mut buffer)
+            .map_err(|e| ChunkError::Transient(AppError::network(format!("Read error: {}", e))))?;
+        if n == 0 {
+            break;
+        }
+
+        {
+            let mut f = file
+                .lock()
+                .map_err(|_| ChunkError::Fatal(AppError::internal("File mutex poisoned")))?;
+            f.seek(SeekFrom::Start(*offset))
+                .map_err(|e| ChunkError::Fatal(AppError::io(format!("Seek error: {}", e))))?;
+            f.write_all(&buffer[..n])
+                .map_err(|e| ChunkError::Fatal(AppError::io(format!("Write error: {}", e))))?;
+        }
+
+        *offset += n as u64;
+        downloaded.fetch_add(n as u64, Ordering::Relaxed);
+    }
+
+    if *offset <= end {
+        return Err(ChunkError::Transient(AppError::network(format!(
+            "Connection closed at byte {} of range ending {}",
+            offset, end
+        ))));
+    }
+
+    Ok(())
+}
+
+// Size bands for chunk_count; each is overridable by the setting of the same name
+pub const DEFAULT_SINGLE_STREAM_BELOW_BYTES: u64 = 4 * 1024 * 1024;
+pub const DEFAULT_TWO_CHUNKS_BELOW_BYTES: u64 = 100 * 1024 * 1024;
+pub const DEFAULT_MAX_DOWNLOAD_CHUNKS: u64 = 4;
+
+/// Ranged connections for a file of `total_size`: one below `single_below`, two below
+/// `two_below`, `max_chunks` above. Small ROMs lose more to extra requests than they gain.
+fn chunks_for_size(total_size: u64, single_below: u64, two_below: u64, max_chunks: u64) -> u64 {
+    let max_chunks = max_chunks.max(1);
+    if total_size < single_below {
+        1
+    } else if total_size < two_below {
+        max_chunks.min(2)
+    } else {
+        max_chunks
+    }
+}
+
+fn chunk_count(total_size: u64) -> Result<u64, AppError> {
+    Ok(chunks_for_size(
+        total_size,
+        read_u64_setting("single_stream_below_bytes", DEFAULT_SINGLE_STREAM_BELOW_BYTES)?,
+        read_u64_setting("two_chunks_below_bytes", DEFAULT_TWO_CHUNKS_BELOW_BYTES)?,
+        read_u64_setting("max_download_chunks", DEFAULT_MAX_DOWNLOAD_CHUNKS)?,
+    ))
+}
+
+fn ranged_parallel_download(
+    client: &Client,
+    window: &Window,
+    id: u32,
+    url: &str,
+    file_path: &Path,
+    remote: &RemoteInfo,
+) -> Result<(), AppError> {
+    let total_size = if remote.total_size > 0 && remote.accepts_ranges {
+        remote.total_size
+    } else {
+        match probe_range_total(client, url) {
+            Some(total) => total,
+            // Size truly unknown; one stream with indeterminate progress
+            None => return single_stream_download(client, window, id, url, file_path),
+        }
+    };
+
+    let chunks = chunk_count(total_size)?;
+    if chunks == 1 {
         return single_stream_download(client, window, id, url, file_path);
     }
 
@@ -177,15 +853,14 @@ fn ranged_parallel_download_4(
         .write(true)
         .read(true)
         .open(file_path)
-        .map_err(|e| format!("File open error: {}", e))?;
+        .map_err(|e| AppError::io(format!("File open error: {}", e)))?;
 
     file.set_len(total_size)
-        .map_err(|e| format!("Failed to set file size: {}", e))?;
+        .map_err(|e| AppError::io(format!("Failed to set file size: {}", e)))?;
 
     let file = Arc::new(Mutex::new(file));
     let downloaded = Arc::new(AtomicU64::new(0));
 
-    let chunks = 4u64;
     let chunk_size = (total_size + chunks - 1) / chunks;
     let mut handles = Vec::new();
 
@@ -201,41 +876,40 @@ fn ranged_parallel_download_4(
         let file = Arc::clone(&file);
         let downloaded = Arc::clone(&downloaded);
 
-        let handle = std::thread::spawn(move || -> Result<(), String> {
-            let range_value = format!("bytes={}-{}", start, end);
-
-            let mut resp = client
-                .get(&url)
-                .header(RANGE, range_value)
-                .send()
-                .map_err(|e| format!("Range request failed: {}", e))?;
-
-            if !(resp.status().as_u16() == 206 || resp.status().is_success()) {
-                return Err(format!("Range HTTP error: {}", resp.status()));
-            }
+        let window = window.clone();
 
+        let handle = std::thread::spawn(move || -> Result<(), AppError> {
             let mut offset = start;
-            let mut buffer = [0u8; 32 * 1024];
+            let mut attempt = 0u32;
 
             loop {
-                let n = resp.read(&mut buffer).map_err(|e| format!("Read error: {}", e))?;
-                if n == 0 {
-                    break;
-                }
-
-                {
-                    let mut f = file.lock().map_err(|_| "File mutex poisoned".to_string())?;
-                    f.seek(SeekFrom::Start(offset))
-                        .map_err(|e| format!("Seek error: {}", e))?;
-                    f.write_all(&buffer[..n])
-                        .map_err(|e| format!("Write error: {}", e))?;
+                match fetch_range(&client, id, &url, &mut offset, end, &file, &downloaded) {
+                    Ok(()) => return Ok(()),
+                    Err(ChunkError::Fatal(e)) => return Err(e),
+                    Err(ChunkError::Transient(e)) => {
+                        if attempt >= CHUNK_MAX_RETRIES {
+                            return Err(e);
+                        }
+
+                        // Re-issue the range from where we stopped after 1s, 2s, 4s...
+                        let delay_ms = CHUNK_RETRY_BASE_MS << attempt;
+                        attempt += 1;
+
+                        let _ = window.emit(
+                            "download-retrying",
+                            DownloadRetryingPayload {
+                                id,
+                                attempt,
+                                max_attempts: CHUNK_MAX_RETRIES,
+                                delay_ms,
+                                error: e.to_string(),
+                            },
+                        );
+
+                        std::thread::sleep(std::time::Duration::from_millis(delay_ms));
+                    }
                 }
-
-                offset += n as u64;
-                downloaded.fetch_add(n as u64, Ordering::Relaxed);
             }
-
-            Ok(())
         });
 
         handles.push(handle);
@@ -243,10 +917,12 @@ fn ranged_parallel_download_4(
 
     loop {
         let done_bytes = downloaded.load(Ordering::Relaxed);
+        queue::set_bytes(id, done_bytes, total_size);
         let percent = (done_bytes as f64 / total_size as f64) * 100.0;
         emit_progress(window, id, format!("{:.2}%", percent))?;
 
-        if done_bytes >= total_size {
+        // Also stop if every worker exited early (retries exhausted), the join below reports why
+        if done_bytes >= total_size || handles.iter().all(|h| h.is_finished()) {
             break;
         }
 
@@ -256,7 +932,7 @@ fn ranged_parallel_download_4(
     for h in handles {
         match h.join() {
             Ok(res) => res?,
-            Err(_) => return Err("A download thread panicked".to_string()),
+            Err(_) => return Err(AppError::internal("A download thread panicked")),
         }
     }
 
@@ -264,8 +940,44 @@ fn ranged_parallel_download_4(
     Ok(())
 }
 
-// Prevent Zip Slip: ensure zip paths stay inside destination.
-fn safe_join(dest_dir: &Path, entry_name: &str) -> Result<PathBuf, String> {
+// Device names Windows refuses as file names, with or without an extension
+const RESERVED_WINDOWS_NAMES: &[&str] = &[
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// Makes a scraped name safe to use as a single path component on every OS:
+/// separators and characters illegal on Windows become `_`, so it can't climb
+/// out of (or into a subfolder of) the download dir like `safe_join` guards against.
+pub(crate) fn sanitize_file_name(name: &str) -> String {
+    let cleaned: String = name
+        .chars()
+        .map(|c| match c {
+            '<' | '>' | ':' | '"' | '/' | '\\' | '|' | '?' | '*' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+
+    // Windows rejects trailing dots and spaces
+    let cleaned = cleaned.trim_end_matches(['.', ' ']).trim_start();
+    if cleaned.is_empty() {
+        return "Unknown".to_string();
+    }
+
+    let base = cleaned.split('.').next().unwrap_or(cleaned).trim_end();
+    if RESERVED_WINDOWS_NAMES
+        .iter()
+        .any(|r| r.eq_ignore_ascii_case(base))
+    {
+        return format!("_{}", cleaned);
+    }
+
+    cleaned.to_string()
+}
+
+// Prevent Zip Slip: ensure archive paths stay inside destination.
+pub(crate) fn safe_join(dest_dir: &Path, entry_name: &str) -> Result<PathBuf, AppError> {
     let entry_path = Path::new(entry_name);
     let mut clean = PathBuf::new();
 
@@ -274,7 +986,7 @@ fn safe_join(dest_dir: &Path, entry_name: &str) -> Result<PathBuf, String> {
             Component::Normal(part) => clean.push(part),
             Component::CurDir => {}
             Component::RootDir | Component::Prefix(_) | Component::ParentDir => {
-                return Err(format!("Unsafe zip entry path: {}", entry_name));
+                return Err(AppError::io(format!("Unsafe archive entry path: {}", entry_name)));
             }
         }
     }
@@ -282,111 +994,1827 @@ fn safe_join(dest_dir: &Path, entry_name: &str) -> Result<PathBuf, String> {
     Ok(dest_dir.join(clean))
 }
 
-fn extract_zip(zip_path: &Path, dest_dir: &Path) -> Result<(), String> {
-    let zip_file = File::open(zip_path)
-        .map_err(|e| format!("Failed to open zip for extraction: {}", e))?;
+/// An archive entry that couldn't be extracted when `keep_partial_extractions` is on.
+#[derive(Serialize, Clone, Debug)]
+pub struct FailedEntry {
+    pub name: String,
+    pub error: String,
+}
 
-    let mut archive =
-        zip::ZipArchive::new(zip_file).map_err(|e| format!("Invalid zip archive: {}", e))?;
+#[derive(Serialize, Clone, Debug)]
+struct ExtractPartialPayload {
+    id: u32,
+    failed: Vec<FailedEntry>,
+}
 
-    fs::create_dir_all(dest_dir)
-        .map_err(|e| format!("Failed to create extract directory: {}", e))?;
+#[derive(Serialize, Clone, Debug)]
+struct DownloadCorruptPayload {
+    id: u32,
+    archive: String,         // where the archive was kept
+    mismatched: Vec<String>, // entries missing or not matching the archive's CRC-32
+}
 
-    for i in 0..archive.len() {
-        let mut file = archive
-            .by_index(i)
-            .map_err(|e| format!("Failed reading zip entry: {}", e))?;
+/// A per-entry failure: with `tolerant` it's recorded and extraction carries on,
+/// otherwise it fails the whole extraction.
+fn entry_failed(failed: &mut Vec<FailedEntry>, tolerant: bool, name: &str, e: AppError) -> Result<(), AppError> {
+    if !tolerant {
+        return Err(e);
+    }
+    warn!("Skipping archive entry {}: {}", name, e);
+    failed.push(FailedEntry {
+        name: name.to_string(),
+        error: e.message().to_string(),
+    });
+    Ok(())
+}
+
+fn write_zip_entry(
+    window: &Window,
+    id: u32,
+    index: usize,
+    total: usize,
+    file: &mut zip::read::ZipFile<'_, File>,
+    dest_dir: &Path,
+) -> Result<(), AppError> {
+    let outpath = safe_join(dest_dir, file.name())?;
+    let entry_size = file.size();
+
+    if file.is_dir() {
+        return fs::create_dir_all(&outpath)
+            .map_err(|e| AppError::io(format!("Failed creating dir {:?}: {}", outpath, e)));
+    }
 
-        let outpath = safe_join(dest_dir, file.name())?;
+    if let Some(parent) = outpath.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| AppError::io(format!("Failed creating dir {:?}: {}", parent, e)))?;
+    }
 
-        if file.is_dir() {
-            fs::create_dir_all(&outpath)
-                .map_err(|e| format!("Failed creating dir {:?}: {}", outpath, e))?;
-            continue;
-        }
+    let mut outfile =
+        File::create(&outpath).map_err(|e| AppError::io(format!("Failed creating file {:?}: {}", outpath, e)))?;
 
-        if let Some(parent) = outpath.parent() {
-            fs::create_dir_all(parent)
-                .map_err(|e| format!("Failed creating dir {:?}: {}", parent, e))?;
+    let mut reader = ProgressReader::new(&mut *file, |done| {
+        let _ = emit_extract_progress(window, id, index, total, done, entry_size);
+    });
+    std::io::copy(&mut reader, &mut outfile)
+        .map_err(|e| AppError::io(format!("Failed extracting {:?}: {}", outpath, e)))?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        if let Some(mode) = file.unix_mode() {
+            let _ = fs::set_permissions(&outpath, fs::Permissions::from_mode(mode));
         }
+    }
 
-        let mut outfile =
-            File::create(&outpath).map_err(|e| format!("Failed creating file {:?}: {}", outpath, e))?;
-
-        std::io::copy(&mut file, &mut outfile)
-            .map_err(|e| format!("Failed extracting {:?}: {}", outpath, e))?;
+    Ok(())
+}
 
-        #[cfg(unix)]
-        {
-            use std::os::unix::fs::PermissionsExt;
-            if let Some(mode) = file.unix_mode() {
-                let _ = fs::set_permissions(&outpath, fs::Permissions::from_mode(mode));
+fn extract_zip(
+    window: &Window,
+    id: u32,
+    zip_path: &Path,
+    dest_dir: &Path,
+    tolerant: bool,
+) -> Result<Vec<FailedEntry>, AppError> {
+    let zip_file = File::open(zip_path)
+        .map_err(|e| AppError::io(format!("Failed to open zip for extraction: {}", e)))?;
+
+    let mut archive =
+        zip::ZipArchive::new(zip_file).map_err(|e| AppError::io(format!("Invalid zip archive: {}", e)))?;
+
+    fs::create_dir_all(dest_dir)
+        .map_err(|e| AppError::io(format!("Failed to create extract directory: {}", e)))?;
+
+    let total = archive.len();
+    let mut failed = Vec::new();
+
+    for i in 0..total {
+        // A broken central directory means no later entry can be trusted either
+        let mut file = archive
+            .by_index(i)
+            .map_err(|e| AppError::io(format!("Failed reading zip entry: {}", e)))?;
+        emit_extract_progress(window, id, i, total, 0, file.size())?;
+
+        if let Err(e) = write_zip_entry(window, id, i, total, &mut file, dest_dir) {
+            entry_failed(&mut failed, tolerant, file.name(), e)?;
+        }
+    }
+
+    Ok(failed)
+}
+
+fn write_7z_entry(
+    window: &Window,
+    id: u32,
+    index: usize,
+    total: usize,
+    entry: &sevenz_rust::SevenZArchiveEntry,
+    data: &mut dyn Read,
+    dest_dir: &Path,
+) -> Result<(), AppError> {
+    let outpath = safe_join(dest_dir, entry.name())?;
+    let entry_size = entry.size();
+
+    if entry.is_directory() {
+        return fs::create_dir_all(&outpath)
+            .map_err(|e| AppError::io(format!("Failed creating dir {:?}: {}", outpath, e)));
+    }
+
+    if let Some(parent) = outpath.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| AppError::io(format!("Failed creating dir {:?}: {}", parent, e)))?;
+    }
+
+    let mut outfile =
+        File::create(&outpath).map_err(|e| AppError::io(format!("Failed creating file {:?}: {}", outpath, e)))?;
+
+    let mut reader = ProgressReader::new(data, |done| {
+        let _ = emit_extract_progress(window, id, index, total, done, entry_size);
+    });
+    std::io::copy(&mut reader, &mut outfile)
+        .map_err(|e| AppError::io(format!("Failed extracting {:?}: {}", outpath, e)))?;
+
+    Ok(())
+}
+
+fn extract_7z(
+    window: &Window,
+    id: u32,
+    archive_path: &Path,
+    dest_dir: &Path,
+    tolerant: bool,
+) -> Result<Vec<FailedEntry>, AppError> {
+    let mut reader = sevenz_rust::SevenZReader::open(archive_path, sevenz_rust::Password::empty())
+        .map_err(|e| AppError::io(format!("Invalid 7z archive: {}", e)))?;
+
+    let total = reader.archive().files.len();
+    let mut index = 0usize;
+
+    fs::create_dir_all(dest_dir)
+        .map_err(|e| AppError::io(format!("Failed to create extract directory: {}", e)))?;
+
+    // for_each_entries only speaks sevenz_rust::Error, so keep our own message around
+    let mut failure: Option<AppError> = None;
+    let mut failed = Vec::new();
+
+    let result = reader.for_each_entries(|entry, data| {
+        let i = index;
+        index += 1;
+        let _ = emit_extract_progress(window, id, i, total, 0, entry.size());
+
+        if let Err(e) = write_7z_entry(window, id, i, total, entry, data, dest_dir) {
+            if let Err(e) = entry_failed(&mut failed, tolerant, entry.name(), e) {
+                failure = Some(e);
+                return Ok(false);
+            }
+            // Solid blocks are read in order, so drain what this entry didn't consume
+            let _ = std::io::copy(data, &mut std::io::sink());
+        }
+
+        Ok(true)
+    });
+
+    if let Some(e) = failure {
+        return Err(e);
+    }
+
+    result.map_err(|e| AppError::io(format!("Failed reading 7z entry: {}", e)))?;
+    Ok(failed)
+}
+
+fn extract_rar(
+    window: &Window,
+    id: u32,
+    archive_path: &Path,
+    dest_dir: &Path,
+    tolerant: bool,
+) -> Result<Vec<FailedEntry>, AppError> {
+    // unrar only streams front to back, so take a listing pass for the total
+    let total = unrar::Archive::new(archive_path)
+        .open_for_listing()
+        .map_err(|e| AppError::io(format!("Invalid rar archive: {}", e)))?
+        .count();
+    let mut index = 0usize;
+    let mut failed = Vec::new();
+
+    let mut archive = unrar::Archive::new(archive_path)
+        .open_for_processing()
+        .map_err(|e| AppError::io(format!("Invalid rar archive: {}", e)))?;
+
+    fs::create_dir_all(dest_dir)
+        .map_err(|e| AppError::io(format!("Failed to create extract directory: {}", e)))?;
+
+    while let Some(header) = archive
+        .read_header()
+        .map_err(|e| AppError::io(format!("Failed reading rar entry: {}", e)))?
+    {
+        let entry_name = header.entry().filename.to_string_lossy().to_string();
+        let entry_size = header.entry().unpacked_size;
+        emit_extract_progress(window, id, index, total, 0, entry_size)?;
+        index += 1;
+
+        // Anything that goes wrong before unrar writes can be skipped; a failed
+        // extract_to consumes the archive handle, so that one always aborts
+        let target = safe_join(dest_dir, &entry_name).and_then(|outpath| {
+            let dir = if header.entry().is_directory() {
+                Some(outpath.as_path())
+            } else {
+                outpath.parent()
+            };
+            if let Some(dir) = dir {
+                fs::create_dir_all(dir).map_err(|e| AppError::io(format!("Failed creating dir {:?}: {}", dir, e)))?;
+            }
+            Ok(outpath)
+        });
+        let outpath = match target {
+            Ok(outpath) if !header.entry().is_directory() => outpath,
+            Ok(_) => {
+                archive = header
+                    .skip()
+                    .map_err(|e| AppError::io(format!("Failed reading rar entry: {}", e)))?;
+                continue;
+            }
+            Err(e) => {
+                entry_failed(&mut failed, tolerant, &entry_name, e)?;
+                archive = header
+                    .skip()
+                    .map_err(|e| AppError::io(format!("Failed reading rar entry: {}", e)))?;
+                continue;
+            }
+        };
+
+        archive = header
+            .extract_to(&outpath)
+            .map_err(|e| AppError::io(format!("Failed extracting {:?}: {}", outpath, e)))?;
+        emit_extract_progress(window, id, index - 1, total, entry_size, entry_size)?;
+    }
+
+    Ok(failed)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum ArchiveKind {
+    Zip,
+    SevenZ,
+    Rar,
+}
+
+/// Sniffs the archive type from its magic bytes, falling back to the extension
+/// when the header isn't recognised.
+fn detect_archive_kind(path: &Path) -> Result<ArchiveKind, AppError> {
+    let mut header = [0u8; 8];
+    let n = File::open(path)
+        .and_then(|mut f| f.read(&mut header))
+        .map_err(|e| AppError::io(format!("Failed to open archive {:?}: {}", path, e)))?;
+    let header = &header[..n];
+
+    if header.starts_with(b"PK\x03\x04") || header.starts_with(b"PK\x05\x06") {
+        return Ok(ArchiveKind::Zip);
+    }
+    if header.starts_with(&[b'7', b'z', 0xBC, 0xAF, 0x27, 0x1C]) {
+        return Ok(ArchiveKind::SevenZ);
+    }
+    if header.starts_with(b"Rar!\x1A\x07") {
+        return Ok(ArchiveKind::Rar);
+    }
+
+    let ext = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("")
+        .to_ascii_lowercase();
+
+    match ext.as_str() {
+        "zip" => Ok(ArchiveKind::Zip),
+        "7z" => Ok(ArchiveKind::SevenZ),
+        "rar" => Ok(ArchiveKind::Rar),
+        _ => Err(AppError::io(format!("Unsupported archive format: {:?}", path))),
+    }
+}
+
+/// (name, CRC-32) of each file entry the archive records a checksum for. Read from the
+/// headers alone, without decompressing anything.
+pub(crate) fn archive_checksums(archive_path: &Path) -> Result<Vec<(String, u32)>, AppError> {
+    let mut checksums = Vec::new();
+    match detect_archive_kind(archive_path)? {
+        ArchiveKind::Zip => {
+            let file = File::open(archive_path)
+                .map_err(|e| AppError::io(format!("Failed to open {:?}: {}", archive_path, e)))?;
+            let mut archive =
+                zip::ZipArchive::new(file).map_err(|e| AppError::io(format!("Invalid zip archive: {}", e)))?;
+            for i in 0..archive.len() {
+                let entry = archive
+                    .by_index_raw(i)
+                    .map_err(|e| AppError::io(format!("Failed reading zip entry: {}", e)))?;
+                if !entry.is_dir() {
+                    checksums.push((entry.name().to_string(), entry.crc32()));
+                }
+            }
+        }
+        ArchiveKind::SevenZ => {
+            let reader = sevenz_rust::SevenZReader::open(archive_path, sevenz_rust::Password::empty())
+                .map_err(|e| AppError::io(format!("Invalid 7z archive: {}", e)))?;
+            for entry in &reader.archive().files {
+                if entry.has_stream && entry.has_crc {
+                    checksums.push((entry.name().to_string(), entry.crc as u32));
+                }
+            }
+        }
+        ArchiveKind::Rar => {
+            let listing = unrar::Archive::new(archive_path)
+                .open_for_listing()
+                .map_err(|e| AppError::io(format!("Invalid rar archive: {}", e)))?;
+            for header in listing {
+                let header = header.map_err(|e| AppError::io(format!("Failed reading rar entry: {}", e)))?;
+                // RAR5 archives hashed with BLAKE2 report no CRC
+                if header.is_directory() || (header.file_crc == 0 && header.unpacked_size > 0) {
+                    continue;
+                }
+                checksums.push((header.filename.to_string_lossy().to_string(), header.file_crc));
+            }
+        }
+    }
+    Ok(checksums)
+}
+
+/// Re-reads what was extracted into `dest_dir` and checks each file against the CRC-32
+/// the archive recorded for it. Returns the entries that are missing or differ.
+fn verify_extraction(window: &Window, id: u32, archive_path: &Path, dest_dir: &Path) -> Result<Vec<String>, AppError> {
+    let checksums = archive_checksums(archive_path)?;
+    let mut mismatched = Vec::new();
+    for (i, (name, crc)) in checksums.iter().enumerate() {
+        emit_progress(window, id, format!("Verifying {}/{}", i + 1, checksums.len()))?;
+        check_cancelled(id)?;
+
+        let path = safe_join(dest_dir, name)?;
+        let matches = path.is_file()
+            && compute_hash(&path, HashAlgorithm::Crc32).is_ok_and(|hash| hash == format!("{:08x}", crc));
+        if !matches {
+            mismatched.push(name.clone());
+        }
+    }
+    Ok(mismatched)
+}
+
+fn emit_corrupt(window: &Window, id: u32, archive: &Path, mismatched: &[String]) {
+    warn!("{} entries of {:?} failed verification", mismatched.len(), archive);
+    let _ = window.emit(
+        "download-corrupt",
+        DownloadCorruptPayload {
+            id,
+            archive: archive.to_string_lossy().to_string(),
+            mismatched: mismatched.to_vec(),
+        },
+    );
+}
+
+/// Extracts into a hidden sibling `.<name>.partial` dir and renames it to `dest_dir`
+/// only once every entry succeeded, so a failed extraction never leaves a folder
+/// that looks complete. With `keep_partial_extractions`, entries that fail are skipped
+/// and returned instead, and the rest still land in `dest_dir` for the user to judge.
+fn extract_archive(
+    window: &Window,
+    id: u32,
+    archive_path: &Path,
+    dest_dir: &Path,
+) -> Result<Vec<FailedEntry>, AppError> {
+    let tolerant = read_bool_setting("keep_partial_extractions", false)?;
+    let kind = detect_archive_kind(archive_path)?;
+
+    let parent = dest_dir
+        .parent()
+        .ok_or_else(|| AppError::io(format!("Invalid extraction folder: {:?}", dest_dir)))?;
+    let name = dest_dir
+        .file_name()
+        .and_then(|n| n.to_str())
+        .ok_or_else(|| AppError::io(format!("Invalid extraction folder: {:?}", dest_dir)))?;
+    let partial_dir = parent.join(format!(".{}.partial", name));
+
+    fs::create_dir_all(parent).map_err(|e| AppError::io(format!("Failed to create folder: {}", e)))?;
+    // Leftover from an earlier crash
+    if partial_dir.exists() {
+        fs::remove_dir_all(&partial_dir)
+            .map_err(|e| AppError::io(format!("Failed to clear {:?}: {}", partial_dir, e)))?;
+    }
+
+    let result = match kind {
+        ArchiveKind::Zip => extract_zip(window, id, archive_path, &partial_dir, tolerant),
+        ArchiveKind::SevenZ => extract_7z(window, id, archive_path, &partial_dir, tolerant),
+        ArchiveKind::Rar => extract_rar(window, id, archive_path, &partial_dir, tolerant),
+    };
+    let failed = match result {
+        Ok(failed) => failed,
+        Err(e) => {
+            let _ = fs::remove_dir_all(&partial_dir);
+            return Err(e);
+        }
+    };
+    // Nothing usable came out, so there's nothing partial to keep
+    if !failed.is_empty() && !library::has_content(&partial_dir) {
+        let _ = fs::remove_dir_all(&partial_dir);
+        return Err(AppError::io(format!(
+            "Every entry failed to extract; first error: {}",
+            failed[0].error
+        )));
+    }
+
+    // A re-download replaces the previous extraction as a whole
+    if dest_dir.exists() {
+        fs::remove_dir_all(dest_dir)
+            .map_err(|e| AppError::io(format!("Failed to replace {:?}: {}", dest_dir, e)))?;
+    }
+    fs::rename(&partial_dir, dest_dir)
+        .map_err(|e| AppError::io(format!("Failed to move extracted files into {:?}: {}", dest_dir, e)))?;
+
+    Ok(failed)
+}
+
+fn game_console(id: u32) -> Result<String, AppError> {
+    let conn = db::open()
+        .map_err(|e| AppError::db(format!("Failed to open DB: {}", e)))?;
+
+    conn.query_row(
+        "SELECT console FROM games WHERE id = ?1",
+        [id as i64],
+        |row| row.get(0),
+    )
+    .map_err(|e| AppError::db(format!("Failed to look up console for game {}: {}", id, e)))
+}
+
+/// Tells the UI the link is gone and, if `refresh_stale_links` is on, re-scrapes the
+/// game's console so a retry picks up the new link.
+fn handle_stale_link(window: &Window, id: u32, url: &str) {
+    let mut refreshed = false;
+
+    if read_bool_setting("refresh_stale_links", false).unwrap_or(false) {
+        match game_console(id).and_then(|console| data::scrape_console(&console)) {
+            Ok(_) => refreshed = true,
+            Err(e) => warn!("Failed to refresh stale link for {}: {}", id, e),
+        }
+    }
+
+    let _ = window.emit(
+        "download-link-stale",
+        DownloadLinkStalePayload {
+            id,
+            url: url.to_string(),
+            refreshed,
+        },
+    );
+}
+
+/// Name the archive is saved under: the sanitized requested name plus the link's
+/// real extension (zip if it has none).
+pub(crate) fn archive_file_name(file_name: &str, url: &str) -> String {
+    let ext = file_extension(url).unwrap_or_else(|| "zip".to_string());
+    let mut final_file_name = sanitize_file_name(file_name);
+    if !final_file_name
+        .to_ascii_lowercase()
+        .ends_with(&format!(".{}", ext))
+    {
+        final_file_name.push('.');
+        final_file_name.push_str(&ext);
+    }
+    final_file_name
+}
+
+/// Extraction target: downloads_dir/[<console>/]<archive-stem>/
+pub(crate) fn extract_dir_for(
+    downloads_dir: &Path,
+    archive_name: &str,
+    console: Option<&str>,
+    by_console: bool,
+) -> PathBuf {
+    let stem = Path::new(archive_name)
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("extracted");
+
+    let mut extract_dir = downloads_dir.to_path_buf();
+    if by_console {
+        if let Some(console) = console {
+            extract_dir.push(sanitize_file_name(console));
+        }
+    }
+    extract_dir.push(stem);
+    extract_dir
+}
+
+/// Everything one download needs, as passed in from the UI.
+struct DownloadJob {
+    id: u32,
+    url: String,
+    file_name: String,
+    save_as: Option<String>, // replaces file_name for the saved archive
+    download_dir: Option<String>,
+    zip_dir: Option<String>,     // replaces the download dir for the archive
+    extract_dir: Option<String>, // replaces the download dir for extracted files
+    extract: Option<bool>,
+    retry_of: Option<i64>, // already requeued history row to reuse instead of starting a new one
+    force: bool,           // redownload over an existing copy
+    flat: bool,  // extract straight into the download dir instead of a folder per game
+}
+
+fn run_download(window: &Window, record: i64, job: DownloadJob) -> Result<String, AppError> {
+    let DownloadJob {
+        id,
+        url,
+        file_name,
+        save_as,
+        download_dir,
+        zip_dir,
+        extract_dir,
+        extract,
+        force,
+        flat,
+        ..
+    } = job;
+    let url = apply_mirror(&url)?;
+
+    // A split archive is fetched part by part and joined before extracting; names and the
+    // extension come from the joined archive ("Game.zip"), not part 1 ("Game.zip.001")
+    let parts = split_archive_parts(id, &url)?;
+    let archive_url = match data::split_part_suffix(&url) {
+        Some((base, _)) if !parts.is_empty() => base.to_string(),
+        _ => url.clone(),
+    };
+
+    // Resolve where the archive and the extracted files go (overrides, console dir,
+    // zip_dir/extract_dir, saved download dir or default)
+    let console = game_console(id).ok();
+    let (zip_root, extract_root) = resolve_download_targets(download_dir, zip_dir, extract_dir, console.as_deref())?;
+
+    let should_extract = match extract {
+        Some(v) => v,
+        None => read_bool_setting("auto_extract", true)?,
+    };
+
+    // Fail before fetching anything rather than after the archive landed
+    ensure_writable_dir(&zip_root)?;
+    if should_extract {
+        ensure_writable_dir(&extract_root)?;
+    }
+
+    // With a staging dir, everything is fetched and extracted there and only moved into
+    // the library once it's complete. A forced redownload is always staged, so the old
+    // copy stays in place until the new one is ready to replace it.
+    let configured = staging_dir()?.filter(|dir| *dir != zip_root);
+    let scratch = (configured.is_none() && force)
+        .then(|| RemoveOnDrop(zip_root.join(format!(".redownload-{}", id))));
+    let staging = configured.or_else(|| scratch.as_ref().map(|dir| dir.0.clone()));
+    let work_dir = staging.clone().unwrap_or_else(|| zip_root.clone());
+    fs::create_dir_all(&work_dir)
+        .map_err(|e| AppError::io(format!("Failed to create folder: {}", e)))?;
+    // Unstaged, extraction writes straight into the extract root, possibly on another volume
+    let extract_base = if staging.is_some() { work_dir.clone() } else { extract_root.clone() };
+
+    let wanted_name = save_as
+        .filter(|n| !n.trim().is_empty())
+        .unwrap_or(file_name);
+    let ext = file_extension(&archive_url).unwrap_or_else(|| "zip".to_string());
+
+    // Another running download may already be writing the same file; take the next free name
+    let claim = queue::claim_path(&work_dir, &archive_file_name(&wanted_name, &archive_url), &ext);
+    let final_file_name = claim.file_name();
+    let zip_path = work_dir.join(&final_file_name);
+
+    info!("Downloading from: {}", url);
+    info!("Saving archive to: {:?}", zip_path);
+
+    // Sources that gate access get their configured headers on every request
+    let client = source_client(console.as_deref())?;
+
+    // Check the target volumes up front; extraction needs roughly the archive size again
+    let remotes = if parts.is_empty() {
+        vec![probe_remote(&client, &url)?]
+    } else {
+        parts.iter().map(|part| probe_remote(&client, part)).collect::<Result<Vec<_>, _>>()?
+    };
+    // Only trusted when every part reported its size
+    let total_size = if remotes.iter().all(|r| r.total_size > 0) {
+        remotes.iter().map(|r| r.total_size).sum()
+    } else {
+        0
+    };
+    if total_size > 0 {
+        // Parts and the joined archive sit side by side until the join finishes
+        let archive_needed = if parts.is_empty() { total_size } else { total_size.saturating_mul(2) };
+        let extract_needed = if should_extract { total_size } else { 0 };
+        if extract_base == work_dir {
+            ensure_free_space(&work_dir, archive_needed.saturating_add(extract_needed))?;
+        } else {
+            ensure_free_space(&work_dir, archive_needed)?;
+            ensure_free_space(&extract_base, extract_needed)?;
+        }
+    }
+
+    // Download archive (chunked with fallback, unless the user forced one connection)
+    history::set_state(record, DownloadState::Downloading)?;
+    queue::set_state(id, DownloadState::Downloading);
+    let fetched = if parts.is_empty() {
+        fetch_archive(&client, window, id, &url, &zip_path, &remotes[0])
+    } else {
+        fetch_split_archive(&client, window, id, &parts, &remotes, &zip_path)
+    };
+    if let Err(e) = fetched {
+        // A cancelled archive is never resumed, so don't leave the partial file behind
+        if matches!(e, AppError::Cancelled { .. }) {
+            let _ = fs::remove_file(&zip_path);
+        }
+        return Err(e);
+    }
+
+    if let Ok(meta) = fs::metadata(&zip_path) {
+        history::set_bytes(record, meta.len())?;
+    }
+    // Split archives aren't checked for updates; one part's validators don't cover the rest
+    if let [remote] = remotes.as_slice() {
+        if parts.is_empty() {
+            history::set_validators(record, remote.etag.as_deref(), remote.last_modified.as_deref())?;
+        }
+    }
+
+    if !should_extract {
+        if force {
+            remove_old_copy(id)?;
+        }
+        let zip_path = match &staging {
+            Some(_) => {
+                let library_path = zip_root.join(&final_file_name);
+                move_into_library(&zip_path, &library_path)?;
+                library_path
             }
+            None => zip_path,
+        };
+        history::set_path(record, &zip_path)?;
+        finish_download(window, id)?;
+        return Ok(format!("Downloaded to {:?}", zip_path));
+    }
+
+    let by_console = read_bool_setting("organize_by_console", true)?;
+    let extract_dir = if flat {
+        // Unpacked into a hidden folder first and then spread into the destination by place_flat
+        extract_base.join(format!(".{}.extracting", final_file_name))
+    } else {
+        extract_dir_for(&extract_base, &final_file_name, console.as_deref(), by_console)
+    };
+
+    check_cancelled(id)?;
+    history::set_state(record, DownloadState::Extracting)?;
+    queue::set_state(id, DownloadState::Extracting);
+    emit_progress(window, id, "Extracting…".to_string())?;
+    let failed_entries = extract_archive(window, id, &zip_path, &extract_dir)?;
+    if failed_entries.is_empty() {
+        emit_progress(window, id, "Extracted".to_string())?;
+    } else {
+        emit_progress(window, id, format!("Extracted ({} entries failed)", failed_entries.len()))?;
+        let _ = window.emit(
+            "extract-partial",
+            ExtractPartialPayload {
+                id,
+                failed: failed_entries.clone(),
+            },
+        );
+    }
+
+    // With verify_before_delete, the extracted files are checked against the archive's
+    // checksums first; a mismatch keeps the archive so extraction can be retried from it
+    let mismatched = if failed_entries.is_empty() && read_bool_setting("verify_before_delete", false)? {
+        let mismatched = verify_extraction(window, id, &zip_path, &extract_dir)?;
+        if mismatched.is_empty() {
+            emit_progress(window, id, "Verified".to_string())?;
+        } else {
+            emit_progress(window, id, format!("Verification failed ({} entries)", mismatched.len()))?;
+        }
+        mismatched
+    } else {
+        Vec::new()
+    };
+
+    // A corrupt extraction fails the download: nothing is placed or marked downloaded, a
+    // forced redownload keeps the old copy, and the archive is kept to retry from
+    if !mismatched.is_empty() {
+        if let Err(e) = fs::remove_dir_all(&extract_dir) {
+            warn!("Failed to remove {:?}: {}", extract_dir, e);
+        }
+        let kept = if staging.is_some() {
+            keep_archive(&zip_path, &zip_root, &final_file_name)?
+        } else {
+            zip_path
+        };
+        emit_corrupt(window, id, &kept, &mismatched);
+        return Err(AppError::io(format!(
+            "{} entries failed verification; the archive was kept at {:?}",
+            mismatched.len(),
+            kept
+        )));
+    }
+
+    // Only reached after a successful extraction, so a failed one always keeps the archive;
+    // so does a partial one, in case the user wants to retry the missing entries
+    let zip_removed = failed_entries.is_empty() && read_bool_setting("delete_zip_after_extract", true)?;
+    if zip_removed {
+        if let Err(e) = fs::remove_file(&zip_path) {
+            warn!("Failed to remove archive {:?}: {}", zip_path, e);
+        }
+    }
+
+    // The new copy is fetched and extracted, so the old one can go before it's placed
+    if force {
+        remove_old_copy(id)?;
+    }
+
+    if flat {
+        let zip_path = if staging.is_some() && !zip_removed {
+            let library_zip = zip_root.join(&final_file_name);
+            move_into_library(&zip_path, &library_zip)?;
+            library_zip
+        } else {
+            zip_path
+        };
+        let placed = place_flat(&extract_dir, &extract_root)?;
+
+        // Other games share the folder, so only a lone file or folder is recorded as this
+        // game's; loose multi-file sets are left for the user to manage
+        if let [only] = placed.as_slice() {
+            history::set_path(record, only)?;
+        }
+        finish_download(window, id)?;
+
+        if !failed_entries.is_empty() {
+            return Ok(format!(
+                "Extracted {} files into {:?} with {} entries missing; the archive was kept at {:?}",
+                placed.len(),
+                extract_root,
+                failed_entries.len(),
+                zip_path
+            ));
+        }
+        return Ok(format!("Extracted {} files into {:?}", placed.len(), extract_root));
+    }
+
+    let (zip_path, extract_dir) = match &staging {
+        Some(_) => {
+            let library_extract = extract_dir_for(&extract_root, &final_file_name, console.as_deref(), by_console);
+            move_into_library(&extract_dir, &library_extract)?;
+            let library_zip = zip_root.join(&final_file_name);
+            if !zip_removed {
+                move_into_library(&zip_path, &library_zip)?;
+            }
+            (library_zip, library_extract)
+        }
+        None => (zip_path, extract_dir),
+    };
+
+    // Mark downloaded only after successful extraction
+    history::set_path(record, &extract_dir)?;
+    finish_download(window, id)?;
+
+    if !failed_entries.is_empty() {
+        return Ok(format!(
+            "Extracted to {:?} with {} of its entries missing; the archive was kept at {:?}",
+            extract_dir,
+            failed_entries.len(),
+            zip_path
+        ));
+    }
+    if zip_removed {
+        return Ok(format!("Extracted to {:?}", extract_dir));
+    }
+
+    Ok(format!(
+        "Downloaded to {:?} and extracted to {:?}",
+        zip_path, extract_dir
+    ))
+}
+
+/// Moves a staged archive into `zip_root` without replacing anything there, e.g. the
+/// archive of the copy a failed redownload leaves in place. Returns where it ended up.
+fn keep_archive(zip_path: &Path, zip_root: &Path, file_name: &str) -> Result<PathBuf, AppError> {
+    let mut dest = zip_root.join(file_name);
+    let mut n = 1;
+    while dest.exists() {
+        dest = zip_root.join(numbered_name(file_name, n));
+        n += 1;
+    }
+    move_into_library(zip_path, &dest)?;
+    Ok(dest)
+}
+
+/// Deletes what an earlier download of game `id` left in the library and clears its
+/// downloaded flag, ahead of placing a forced redownload.
+fn remove_old_copy(id: u32) -> Result<(), AppError> {
+    if let Some(game) = get_game(id as i64)? {
+        library::remove_game_files(&game)?;
+        unmark_downloaded(id)?;
+    }
+    Ok(())
+}
+
+/// A scratch folder deleted, with whatever is left in it, when the download ends.
+struct RemoveOnDrop(PathBuf);
+
+impl Drop for RemoveOnDrop {
+    fn drop(&mut self) {
+        if self.0.exists() {
+            if let Err(e) = fs::remove_dir_all(&self.0) {
+                warn!("Failed to remove {:?}: {}", self.0, e);
+            }
+        }
+    }
+}
+
+/// Mirrored links of game `id`'s parts when it's a split archive whose part 1 is `url`;
+/// empty otherwise, including when the stored parts are out of date.
+fn split_archive_parts(id: u32, url: &str) -> Result<Vec<String>, AppError> {
+    let conn = db::open().map_err(|e| AppError::db(format!("Failed to open DB: {}", e)))?;
+    let parts = data::game_parts(&conn, id as i64)
+        .map_err(|e| AppError::db(format!("Failed to read parts of game {}: {}", id, e)))?
+        .iter()
+        .map(|part| apply_mirror(part))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    if parts.first().map(String::as_str) != Some(url) {
+        return Ok(Vec::new());
+    }
+    Ok(parts)
+}
+
+/// Downloads one file, chunked with fallback unless the user forced one connection.
+fn fetch_archive(
+    client: &Client,
+    window: &Window,
+    id: u32,
+    url: &str,
+    path: &Path,
+    remote: &RemoteInfo,
+) -> Result<(), AppError> {
+    if read_setting("download_mode")?.as_deref() == Some(DOWNLOAD_MODE_SINGLE) {
+        single_stream_download(client, window, id, url, path)
+    } else {
+        ranged_parallel_download(client, window, id, url, path, remote)
+    }
+}
+
+/// Downloads every part of a split archive next to `zip_path` and joins them into it
+/// in order. The part files are removed once joined, or on failure.
+fn fetch_split_archive(
+    client: &Client,
+    window: &Window,
+    id: u32,
+    parts: &[String],
+    remotes: &[RemoteInfo],
+    zip_path: &Path,
+) -> Result<(), AppError> {
+    let part_paths: Vec<PathBuf> = (1..=parts.len())
+        .map(|n| PathBuf::from(format!("{}.{:03}", zip_path.to_string_lossy(), n)))
+        .collect();
+    let remove_parts = || {
+        for path in &part_paths {
+            let _ = fs::remove_file(path);
+        }
+    };
+
+    let joined = (|| -> Result<(), AppError> {
+        for (i, ((url, remote), path)) in parts.iter().zip(remotes).zip(&part_paths).enumerate() {
+            emit_progress(window, id, format!("Downloading part {}/{}", i + 1, parts.len()))?;
+            fetch_archive(client, window, id, url, path, remote)?;
+        }
+
+        emit_progress(window, id, format!("Joining {} parts…", parts.len()))?;
+        let mut joined = File::create(zip_path)
+            .map_err(|e| AppError::io(format!("Failed to create {:?}: {}", zip_path, e)))?;
+        for path in &part_paths {
+            check_cancelled(id)?;
+            let mut part = File::open(path).map_err(|e| AppError::io(format!("Failed to open {:?}: {}", path, e)))?;
+            std::io::copy(&mut part, &mut joined)
+                .map_err(|e| AppError::io(format!("Failed to join {:?}: {}", path, e)))?;
+        }
+        joined.sync_all().map_err(|e| AppError::io(format!("Failed to write {:?}: {}", zip_path, e)))
+    })();
+
+    remove_parts();
+    joined
+}
+
+/// The `staging_dir` setting: where downloads are fetched and extracted before they're
+/// moved into the library. `None` means they go straight into the library.
+pub(crate) fn staging_dir() -> Result<Option<PathBuf>, AppError> {
+    Ok(read_setting("staging_dir")?
+        .filter(|dir| !dir.trim().is_empty())
+        .map(PathBuf::from))
+}
+
+fn copy_recursively(src: &Path, dest: &Path) -> std::io::Result<()> {
+    if !src.is_dir() {
+        return fs::copy(src, dest).map(|_| ());
+    }
+    fs::create_dir_all(dest)?;
+    for entry in fs::read_dir(src)? {
+        let entry = entry?;
+        copy_recursively(&entry.path(), &dest.join(entry.file_name()))?;
+    }
+    Ok(())
+}
+
+/// Moves a finished file or folder from staging into the library, replacing whatever
+/// was at `dest`. A plain rename on the same volume; across volumes it's copied to a
+/// hidden sibling first and renamed into place, so `dest` never holds a partial copy.
+fn move_into_library(src: &Path, dest: &Path) -> Result<(), AppError> {
+    let parent = dest
+        .parent()
+        .ok_or_else(|| AppError::io(format!("Invalid library path: {:?}", dest)))?;
+    let name = dest
+        .file_name()
+        .ok_or_else(|| AppError::io(format!("Invalid library path: {:?}", dest)))?;
+    fs::create_dir_all(parent).map_err(|e| AppError::io(format!("Failed to create folder: {}", e)))?;
+
+    let remove = |path: &Path| {
+        if path.is_dir() {
+            fs::remove_dir_all(path)
+        } else {
+            fs::remove_file(path)
         }
+    };
+
+    if dest.exists() {
+        remove(dest).map_err(|e| AppError::io(format!("Failed to replace {:?}: {}", dest, e)))?;
+    }
+    if fs::rename(src, dest).is_ok() {
+        return Ok(());
+    }
+
+    // Most likely a different volume, which rename can't cross
+    let partial = parent.join(format!(".{}.partial", name.to_string_lossy()));
+    if partial.exists() {
+        let _ = remove(&partial);
+    }
+    if let Err(e) = copy_recursively(src, &partial) {
+        let _ = remove(&partial);
+        return Err(AppError::io(format!("Failed to copy {:?} into the library: {}", src, e)));
     }
+    fs::rename(&partial, dest)
+        .map_err(|e| AppError::io(format!("Failed to move {:?} into the library: {}", src, e)))?;
 
+    if let Err(e) = remove(src) {
+        warn!("Failed to remove staged copy {:?}: {}", src, e);
+    }
     Ok(())
 }
 
+// Held while picking free names in a shared folder, so two games finishing at once
+// can't both take "name.ext"
+static FLAT_PLACEMENT: Mutex<()> = Mutex::new(());
+
+/// `name` with " (n)" before its extension, e.g. "Tetris (1).gb".
+fn numbered_name(name: &str, n: u32) -> String {
+    let path = Path::new(name);
+    match (path.file_stem(), path.extension()) {
+        (Some(stem), Some(ext)) if !stem.is_empty() => {
+            format!("{} ({}).{}", stem.to_string_lossy(), n, ext.to_string_lossy())
+        }
+        _ => format!("{} ({})", name, n),
+    }
+}
+
+/// Moves everything in `src_dir` into `dest_dir`, renaming to "name (1).ext", "name (2).ext", …
+/// when the name is taken, then removes `src_dir`. Returns where each entry ended up.
+fn place_flat(src_dir: &Path, dest_dir: &Path) -> Result<Vec<PathBuf>, AppError> {
+    let entries = fs::read_dir(src_dir)
+        .map_err(|e| AppError::io(format!("Failed to read {:?}: {}", src_dir, e)))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| AppError::io(format!("Failed to read {:?}: {}", src_dir, e)))?;
+
+    let _placing = FLAT_PLACEMENT.lock().unwrap_or_else(|e| e.into_inner());
+    let mut placed = Vec::with_capacity(entries.len());
+    for entry in entries {
+        let name = entry.file_name().to_string_lossy().to_string();
+        let mut dest = dest_dir.join(&name);
+        let mut n = 1;
+        while dest.exists() {
+            dest = dest_dir.join(numbered_name(&name, n));
+            n += 1;
+        }
+        if n > 1 {
+            info!("{:?} already exists in {:?}; saved as {:?}", name, dest_dir, dest);
+        }
+        move_into_library(&entry.path(), &dest)?;
+        placed.push(dest);
+    }
+
+    if let Err(e) = fs::remove_dir_all(src_dir) {
+        warn!("Failed to remove {:?}: {}", src_dir, e);
+    }
+    Ok(placed)
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct DownloadEstimate {
+    pub total_size: Option<u64>, // None when the server doesn't send Content-Length
+    pub accepts_ranges: bool,
+    pub will_extract: bool,
+    pub needed_bytes: u64,
+    pub free_bytes: u64,
+    pub enough_space: bool,
+}
+
+/// HEADs a game's link and checks it against free space, without downloading anything.
+#[tauri::command]
+pub async fn estimate_download(id: u32) -> Result<DownloadEstimate, AppError> {
+    let estimate_task = task::spawn_blocking(move || -> Result<DownloadEstimate, AppError> {
+        let game = get_game(id as i64)?.ok_or_else(|| AppError::not_found(format!("No game with id {}", id)))?;
+
+        let client = source_client(Some(&game.console))?;
+        let remote = probe_remote(&client, &apply_mirror(&game.dl_link)?)?;
+
+        let will_extract = read_bool_setting("auto_extract", true)?;
+        // Same headroom rule as run_download
+        let needed_bytes = if will_extract {
+            remote.total_size.saturating_mul(2)
+        } else {
+            remote.total_size
+        };
+
+        // The folder may not exist yet; measure the volume it will be created on
+        let downloads_dir = resolve_download_dir(None, Some(&game.console))?;
+        let existing = downloads_dir
+            .ancestors()
+            .find(|p| p.exists())
+            .unwrap_or(&downloads_dir);
+        let free_bytes = fs2::available_space(existing)
+            .map_err(|e| AppError::io(format!("Failed to query free space for {:?}: {}", existing, e)))?;
+
+        Ok(DownloadEstimate {
+            total_size: (remote.total_size > 0).then_some(remote.total_size),
+            accepts_ranges: remote.accepts_ranges,
+            will_extract,
+            needed_bytes,
+            free_bytes,
+            enough_space: free_bytes >= needed_bytes,
+        })
+    });
+
+    estimate_task.await.map_err(|e| AppError::internal(e.to_string()))?
+}
+
+// Console name -> total bytes of its available games; dropped when the console is rescraped
+static CONSOLE_SIZES: Mutex<BTreeMap<String, u64>> = Mutex::new(BTreeMap::new());
+
+// Parallel HEAD requests when sizing games the listing gave no size for
+const SIZE_PROBE_WORKERS: usize = 8;
+
+#[derive(Serialize, Clone, Debug)]
+struct ConsoleSizeProgressPayload {
+    console: String,
+    checked: usize,
+    total: usize,
+}
+
+/// Forgets `console`'s cached total, e.g. after its listing changed.
+pub(crate) fn forget_console_size(console: &str) {
+    CONSOLE_SIZES.lock().unwrap_or_else(|e| e.into_inner()).remove(console);
+}
+
+/// Total bytes of every available game in a console, to check a full set fits before
+/// queueing it. Games the listing gave no size for are HEADed in parallel (emitting
+/// `console-size-progress`) and their sizes saved. Cached until the console is rescraped.
+#[tauri::command]
+pub async fn console_total_size(name: String, window: Window) -> Result<u64, AppError> {
+    task::spawn_blocking(move || console_size(&window, &name))
+        .await
+        .map_err(|e| AppError::internal(e.to_string()))?
+}
+
+fn console_size(window: &Window, console: &str) -> Result<u64, AppError> {
+    if let Some(&cached) = CONSOLE_SIZES.lock().unwrap_or_else(|e| e.into_inner()).get(console) {
+        return Ok(cached);
+    }
+
+    let conn = db::open().map_err(|e| AppError::db(format!("Failed to open DB: {}", e)))?;
+    let exists: i64 = conn
+        .query_row("SELECT COUNT(*) FROM consoles WHERE console = ?1", [console], |row| row.get(0))
+        .map_err(|e| AppError::db(format!("Failed to check console: {}", e)))?;
+    if exists == 0 {
+        return Err(AppError::not_found(format!("No such console: {}", console)));
+    }
+
+    let known: i64 = conn
+        .query_row(
+            "SELECT COALESCE(SUM(size_bytes), 0) FROM games
+             WHERE console = ?1 AND available = 1 AND size_bytes > 0",
+            [console],
+            |row| row.get(0),
+        )
+        .map_err(|e| AppError::db(format!("Failed to sum sizes for {}: {}", console, e)))?;
+
+    let missing: Vec<(i64, String)> = conn
+        .prepare(
+            "SELECT id, dl_link FROM games
+             WHERE console = ?1 AND available = 1 AND (size_bytes IS NULL OR size_bytes <= 0)",
+        )
+        .and_then(|mut stmt| {
+            stmt.query_map([console], |row| Ok((row.get(0)?, row.get(1)?)))?
+                .collect::<Result<Vec<_>, _>>()
+        })
+        .map_err(|e| AppError::db(format!("Failed to list games for {}: {}", console, e)))?;
+
+    let mut total = known.max(0) as u64;
+    let mut complete = true;
+    if !missing.is_empty() {
+        let probed = probe_sizes(window, console, &missing)?;
+        complete = probed.len() == missing.len();
+        total += probed.iter().map(|&(_, size)| size).sum::<u64>();
+        save_probed_sizes(&conn, &probed)?;
+    }
+
+    // A HEAD that failed may work next time, so only a full count is cached
+    if complete {
+        CONSOLE_SIZES
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(console.to_string(), total);
+    }
+    Ok(total)
+}
+
+/// HEADs each (id, link) for its Content-Length. Returns (id, size) for the ones that
+/// answered with a size; failures are logged and left out.
+fn probe_sizes(window: &Window, console: &str, games: &[(i64, String)]) -> Result<Vec<(i64, u64)>, AppError> {
+    let client = source_client(Some(console))?;
+    let jobs = Mutex::new(games.iter());
+    let sizes = Mutex::new(Vec::new());
+    let checked = AtomicU64::new(0);
+
+    std::thread::scope(|scope| {
+        for _ in 0..SIZE_PROBE_WORKERS.min(games.len()) {
+            scope.spawn(|| loop {
+                let next = jobs.lock().unwrap_or_else(|e| e.into_inner()).next();
+                let Some((id, link)) = next else {
+                    break;
+                };
+
+                match apply_mirror(link).and_then(|url| probe_remote(&client, &url)) {
+                    Ok(remote) if remote.total_size > 0 => {
+                        sizes.lock().unwrap_or_else(|e| e.into_inner()).push((*id, remote.total_size));
+                    }
+                    Ok(_) => warn!("No Content-Length for game {}", id),
+                    Err(e) => warn!("Failed to size game {}: {}", id, e),
+                }
+
+                let done = checked.fetch_add(1, Ordering::Relaxed) + 1;
+                let _ = window.emit(
+                    "console-size-progress",
+                    ConsoleSizeProgressPayload {
+                        console: console.to_string(),
+                        checked: done as usize,
+                        total: games.len(),
+                    },
+                );
+            });
+        }
+    });
+
+    Ok(sizes.into_inner().unwrap_or_else(|e| e.into_inner()))
+}
+
+fn save_probed_sizes(conn: &Connection, sizes: &[(i64, u64)]) -> Result<(), AppError> {
+    let tx = conn
+        .unchecked_transaction()
+        .map_err(|e| AppError::db(format!("Failed to start transaction: {}", e)))?;
+    for &(id, size) in sizes {
+        tx.execute(
+            "UPDATE games SET size_bytes = ?1 WHERE id = ?2",
+            rusqlite::params![size as i64, id],
+        )
+        .map_err(|e| AppError::db(format!("Failed to save size for game {}: {}", id, e)))?;
+    }
+    tx.commit().map_err(|e| AppError::db(format!("Failed to save sizes: {}", e)))
+}
+
+// Parallel conditional HEADs when checking downloaded games for updates
+const UPDATE_CHECK_WORKERS: usize = 8;
+
+/// A finished download and the validators its archive was served with.
+struct DownloadedGame {
+    id: u32,
+    dl_link: String,
+    etag: Option<String>,
+    last_modified: Option<String>,
+}
+
+/// Whether the mirror's copy of `game` differs from the one downloaded. A 304 means
+/// unchanged; a server that ignores the condition is judged on the validators it sends.
+fn remote_changed(client: &Client, game: &DownloadedGame) -> Result<bool, AppError> {
+    let url = apply_mirror(&game.dl_link)?;
+    let request = match (&game.etag, &game.last_modified) {
+        (Some(etag), _) => client.head(&url).header(IF_NONE_MATCH, etag),
+        (None, Some(modified)) => client.head(&url).header(IF_MODIFIED_SINCE, modified),
+        (None, None) => return Ok(false),
+    };
+
+    let _permit = connection_permit();
+    let head = request.send().map_err(|e| AppError::network(format!("HEAD failed: {}", e)))?;
+    if head.status() == StatusCode::NOT_MODIFIED {
+        return Ok(false);
+    }
+    if !head.status().is_success() {
+        return Err(AppError::network(format!("HEAD HTTP error: {}", head.status())));
+    }
+
+    let remote = remote_info(head.headers());
+    Ok(match (&game.etag, &remote.etag) {
+        (Some(ours), Some(theirs)) => ours != theirs,
+        _ => game.last_modified != remote.last_modified,
+    })
+}
+
+fn downloaded_games() -> Result<BTreeMap<String, Vec<DownloadedGame>>, AppError> {
+    let conn = db::open().map_err(|e| AppError::db(format!("Failed to open DB: {}", e)))?;
+    let rows = conn
+        .prepare(
+            "SELECT g.console, g.id, g.dl_link, d.etag, d.last_modified
+             FROM downloads d
+             JOIN games g ON g.name = d.game_name AND g.console = d.console
+             WHERE d.id IN (SELECT MAX(id) FROM downloads WHERE state = ?1 GROUP BY game_name, console)
+               AND g.is_downloaded = 1
+               AND (d.etag IS NOT NULL OR d.last_modified IS NOT NULL)",
+        )
+        .and_then(|mut stmt| {
+            stmt.query_map([DownloadState::Done.as_str()], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    DownloadedGame {
+                        id: row.get(1)?,
+                        dl_link: row.get(2)?,
+                        etag: row.get(3)?,
+                        last_modified: row.get(4)?,
+                    },
+                ))
+            })?
+            .collect::<Result<Vec<_>, _>>()
+        })
+        .map_err(|e| AppError::db(format!("Failed to list downloaded games: {}", e)))?;
+
+    let mut by_console: BTreeMap<String, Vec<DownloadedGame>> = BTreeMap::new();
+    for (console, game) in rows {
+        by_console.entry(console).or_default().push(game);
+    }
+    Ok(by_console)
+}
+
+/// Ids of downloaded games whose file changed on the mirror since it was fetched, found
+/// with conditional HEAD requests. Games downloaded before validators were recorded,
+/// split archives and links that fail to answer are left out.
+#[tauri::command]
+pub async fn check_for_updates() -> Result<Vec<u32>, AppError> {
+    task::spawn_blocking(|| -> Result<Vec<u32>, AppError> {
+        let changed = Mutex::new(Vec::new());
+
+        for (console, games) in downloaded_games()? {
+            let client = source_client(Some(&console))?;
+            let jobs = Mutex::new(games.iter());
+
+            std::thread::scope(|scope| {
+                for _ in 0..UPDATE_CHECK_WORKERS.min(games.len()) {
+                    scope.spawn(|| loop {
+                        let next = jobs.lock().unwrap_or_else(|e| e.into_inner()).next();
+                        let Some(game) = next else {
+                            break;
+                        };
+
+                        match remote_changed(&client, game) {
+                            Ok(true) => changed.lock().unwrap_or_else(|e| e.into_inner()).push(game.id),
+                            Ok(false) => {}
+                            Err(e) => warn!("Failed to check game {} for updates: {}", game.id, e),
+                        }
+                    });
+                }
+            });
+        }
+
+        let mut changed = changed.into_inner().unwrap_or_else(|e| e.into_inner());
+        changed.sort_unstable();
+        Ok(changed)
+    })
+    .await
+    .map_err(|e| AppError::internal(e.to_string()))?
+}
+
+/// Queues one download behind the concurrency limit and records its outcome.
+/// A second request for a game that's already queued or running is rejected.
+fn download_one(window: &Window, job: DownloadJob) -> Result<String, AppError> {
+    let id = job.id;
+
+    // Claim the id before touching any files: a forced redownload replaces the old copy,
+    // and two workers would otherwise write the same archive
+    if !maintenance::track_download(id)? {
+        return Err(AppError::invalid_input(format!("Game {} is already downloading", id)));
+    }
+    let result = download_tracked(window, job);
+    queue::untrack(id);
+    result
+}
+
+fn download_tracked(window: &Window, job: DownloadJob) -> Result<String, AppError> {
+    let id = job.id;
+    let url = job.url.clone();
+
+    // A forced redownload replaces the old copy in run_download, once the new one is extracted
+    if let Some(game) = get_game(id as i64)? {
+        if game.is_downloaded && !job.force {
+            // A retried row has nothing left to do
+            if let Some(record) = job.retry_of {
+                history::finish_record(record, DownloadState::Cancelled, None)?;
+            }
+            window
+                .emit("download-complete", DownloadCompletePayload { id })
+                .map_err(|e| AppError::internal(format!("Emit failed: {}", e)))?;
+            return Ok("Already downloaded".to_string());
+        }
+    }
+
+    // Every attempt gets a history row that ends up done or failed; a retry reuses its own
+    let record = match job.retry_of {
+        Some(record) => record,
+        None => history::start_record(id)?,
+    };
+    let result = match queue::acquire_slot(id) {
+        Some(_slot) => run_download(window, record, job),
+        None if queue::is_dequeued(id) => Err(AppError::cancelled("Removed from the queue")),
+        None => Err(AppError::cancelled("Download cancelled")),
+    };
+
+    match &result {
+        Ok(_) => history::finish_record(record, DownloadState::Done, None)?,
+        // dequeue_download already told the UI
+        Err(AppError::Cancelled { .. }) if queue::is_dequeued(id) => {
+            history::finish_record(record, DownloadState::Cancelled, None)?;
+        }
+        Err(AppError::Cancelled { .. }) => {
+            info!("Download {} cancelled", id);
+            history::finish_record(record, DownloadState::Cancelled, None)?;
+            let _ = window.emit("download-cancelled", DownloadCancelledPayload { id });
+        }
+        Err(e) => {
+            error!("Download {} failed: {}", id, e);
+            if matches!(e, AppError::Stale { .. }) {
+                handle_stale_link(window, id, &url);
+            }
+            history::finish_record(record, DownloadState::Failed, Some(e.message()))?;
+        }
+    }
+
+    result
+}
+
 #[tauri::command(rename_all = "camelCase")]
+#[allow(clippy::too_many_arguments)] // flat optional args keep existing invoke calls working
 pub async fn download_file(
     url: String,
     file_name: String,
     id: u32,
     download_dir: Option<String>, // <-- pass-through from UI (optional)
+    extract: Option<bool>,        // <-- overrides the auto_extract setting
+    save_as: Option<String>,      // <-- custom name for the saved archive
+    force: Option<bool>,          // <-- delete the old copy and fetch again
+    zip_dir: Option<String>,      // <-- where the archive goes instead of the download dir
+    extract_dir: Option<String>,  // <-- where it's extracted instead of the download dir
     window: Window,
-) -> Result<String, String> {
-    let download_task = task::spawn_blocking(move || -> Result<String, String> {
-        // Resolve downloads dir (override or saved setting or default)
-        let downloads_dir = resolve_download_dir(download_dir)?;
-        fs::create_dir_all(&downloads_dir)
-            .map_err(|e| format!("Failed to create folder: {}", e))?;
+) -> Result<String, AppError> {
+    let download_task = task::spawn_blocking(move || -> Result<String, AppError> {
+        let job = DownloadJob {
+            id,
+            url,
+            file_name,
+            save_as,
+            download_dir,
+            zip_dir,
+            extract_dir,
+            extract,
+            retry_of: None,
+            force: force.unwrap_or(false),
+            flat: false,
+        };
+        download_one(&window, job)
+    });
+
+    download_task.await.map_err(|e| AppError::internal(e.to_string()))?
+}
+
+#[derive(Serialize, Clone, Debug, Default)]
+pub struct BatchSummary {
+    pub succeeded: Vec<u32>,
+    pub failed: Vec<(u32, AppError)>,
+}
 
-        // Force .zip
-        let mut final_file_name = file_name.clone();
-        if !final_file_name.to_ascii_lowercase().ends_with(".zip") {
-            final_file_name.push_str(".zip");
+// How often download_games emits `batch-progress` when asked to
+const BATCH_PROGRESS_INTERVAL: std::time::Duration = std::time::Duration::from_millis(500);
+
+/// Emits `batch-progress` for `ids` every `BATCH_PROGRESS_INTERVAL` until all of them
+/// are in `finished` (id -> succeeded), then once more with the final totals.
+fn report_batch_progress(window: &Window, ids: &[u32], finished: &Mutex<BTreeMap<u32, bool>>) {
+    // Last bytes/total seen per id, since finished downloads drop out of the queue registry
+    let mut seen: BTreeMap<u32, (u64, u64)> = BTreeMap::new();
+
+    loop {
+        let live = queue::progress_of(ids);
+        let finished = finished.lock().unwrap_or_else(|e| e.into_inner()).clone();
+
+        for (&id, &(_, downloaded, total)) in &live {
+            seen.insert(id, (downloaded, total));
+        }
+        // The last sample can trail the real end of a successful download
+        for (id, _) in finished.iter().filter(|(_, &ok)| ok) {
+            if let Some((downloaded, total)) = seen.get_mut(id) {
+                *downloaded = (*downloaded).max(*total);
+            }
         }
 
-        let zip_path = downloads_dir.join(&final_file_name);
+        let payload = BatchProgressPayload {
+            total: ids.len(),
+            completed: finished.len(),
+            in_progress: live
+                .iter()
+                .filter(|(id, (state, _, _))| *state != DownloadState::Queued && !finished.contains_key(id))
+                .count(),
+            overall_bytes: seen.values().map(|(downloaded, _)| downloaded).sum(),
+            overall_total: seen.values().map(|(_, total)| total).sum(),
+        };
+        let _ = window.emit("batch-progress", payload);
+
+        if finished.len() >= ids.len() {
+            return;
+        }
+        std::thread::sleep(BATCH_PROGRESS_INTERVAL);
+    }
+}
 
-        println!("Downloading from: {}", url);
-        println!("Saving zip to: {:?}", zip_path);
+/// Downloads several games; each waits for a queue slot like `download_file`
+/// and a failure doesn't stop the rest. Per-id events are always emitted; with
+/// `aggregate_progress` a throttled `batch-progress` summary is emitted as well.
+/// `extract_flat` puts every game's files straight into the download dir (one ROM
+/// folder, as frontends expect), numbering any names that collide.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn download_games(
+    mut ids: Vec<u32>,
+    download_dir: Option<String>,
+    aggregate_progress: Option<bool>,
+    extract_flat: Option<bool>,
+    window: Window,
+) -> Result<BatchSummary, AppError> {
+    let extract_flat = extract_flat.unwrap_or(false);
+    // Progress counts finished ids, so a repeated one would keep the batch open forever
+    let mut seen = std::collections::HashSet::new();
+    ids.retain(|id| seen.insert(*id));
+
+    // Claimed up front so ids still waiting for a worker show as queued and can be
+    // cancelled; a fixed pool then takes them, and queues them for slots, in request order.
+    // An id that can't be claimed fails on its own instead of failing the batch.
+    let claims: BTreeMap<u32, Result<(), AppError>> = ids
+        .iter()
+        .map(|&id| {
+            let claim = match maintenance::track_download(id) {
+                Ok(true) => Ok(()),
+                Ok(false) => Err(AppError::invalid_input(format!("Game {} is already downloading", id))),
+                Err(e) => Err(e),
+            };
+            (id, claim)
+        })
+        .collect();
+    let batch_task = task::spawn_blocking(move || {
+        let finished: Mutex<BTreeMap<u32, bool>> = Mutex::new(BTreeMap::new());
+
+        let results: Vec<(u32, Result<String, AppError>)> = std::thread::scope(|s| {
+            if aggregate_progress.unwrap_or(false) {
+                let (window, ids, finished) = (&window, &ids, &finished);
+                s.spawn(move || report_batch_progress(window, ids, finished));
+            }
 
-        let client = Client::new();
+            run_in_order(ids.clone(), queue::max_concurrent(), |id| {
+                let claimed = claims[&id].is_ok();
+                let run = || -> Result<String, AppError> {
+                    claims[&id].clone()?;
+                    let game = get_game(id as i64)?
+                        .ok_or_else(|| AppError::not_found(format!("No game with id {}", id)))?;
+                    let job = DownloadJob {
+                        id,
+                        url: game.dl_link,
+                        file_name: game.name,
+                        save_as: None,
+                        download_dir: download_dir.clone(),
+                        zip_dir: None,
+                        extract_dir: None,
+                        extract: None,
+                        retry_of: None,
+                        force: false,
+                        flat: extract_flat,
+                    };
+                    download_tracked(&window, job)
+                };
+                let result = run();
+                if claimed {
+                    queue::untrack(id);
+                }
+                finished
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .insert(id, result.is_ok());
+                (id, result)
+            })
+        });
 
-        // Download zip (chunked with fallback)
-        ranged_parallel_download_4(&client, &window, id, &url, &zip_path)?;
+        let mut summary = BatchSummary::default();
+        for (id, result) in results {
+            match result {
+                Ok(_) => summary.succeeded.push(id),
+                Err(e) => summary.failed.push((id, e)),
+            }
+        }
+        summary
+    });
 
-        // Extract into downloads_dir/<zip-stem>/
-        let stem = Path::new(&final_file_name)
-            .file_stem()
-            .and_then(|s| s.to_str())
-            .unwrap_or("extracted");
+    batch_task.await.map_err(|e| AppError::internal(e.to_string()))
+}
 
-        let extract_dir = downloads_dir.join(stem);
+/// Runs `work` on each item with at most `workers` threads, which take the items in
+/// order. Results come back in the same order.
+fn run_in_order<T: Send, R: Send>(items: Vec<T>, workers: usize, work: impl Fn(T) -> R + Sync) -> Vec<R> {
+    let total = items.len();
+    let jobs = Mutex::new(items.into_iter().enumerate());
+    let results: Mutex<Vec<Option<R>>> = Mutex::new((0..total).map(|_| None).collect());
+
+    std::thread::scope(|scope| {
+        for _ in 0..workers.clamp(1, total.max(1)) {
+            scope.spawn(|| loop {
+                let next = jobs.lock().unwrap_or_else(|e| e.into_inner()).next();
+                let Some((i, item)) = next else {
+                    break;
+                };
+                let result = work(item);
+                results.lock().unwrap_or_else(|e| e.into_inner())[i] = Some(result);
+            });
+        }
+    });
 
-        emit_progress(&window, id, "Extracting…".to_string())?;
-        extract_zip(&zip_path, &extract_dir)?;
-        emit_progress(&window, id, "Extracted".to_string())?;
+    results
+        .into_inner()
+        .unwrap_or_else(|e| e.into_inner())
+        .into_iter()
+        .flatten()
+        .collect()
+}
 
-        // Optional: delete zip after extraction
-        // let _ = fs::remove_file(&zip_path);
+#[derive(Serialize, Clone, Debug, Default)]
+pub struct RetrySummary {
+    pub requeued: usize,
+    pub failed: Vec<(u32, AppError)>, // games whose row couldn't be requeued; it stays failed
+}
 
-        // Mark downloaded only after successful extraction
-        mark_downloaded(id)?;
+/// Re-enqueues every game whose latest download failed, through the normal download
+/// path, reusing each failed history row (reset to queued, error cleared). Returns how
+/// many rows were reset, and which couldn't be; the downloads run in the background and
+/// report as usual.
+#[tauri::command]
+pub fn retry_failed_downloads(window: Window) -> Result<RetrySummary, AppError> {
+    let conn = db::open().map_err(|e| AppError::db(format!("Failed to open DB: {}", e)))?;
+    let failed: Vec<(i64, u32, String, String)> = conn
+        .prepare(
+            "SELECT d.id, g.id, g.dl_link, g.name
+             FROM downloads d
+             JOIN games g ON g.name = d.game_name AND g.console = d.console
+             WHERE d.state = ?1
+               AND d.id IN (SELECT MAX(id) FROM downloads GROUP BY game_name, console)
+               AND g.is_downloaded = 0
+             ORDER BY d.id",
+        )
+        .and_then(|mut stmt| {
+            stmt.query_map([DownloadState::Failed.as_str()], |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
+            })?
+            .collect::<Result<Vec<_>, _>>()
+        })
+        .map_err(|e| AppError::db(format!("Failed to list failed downloads: {}", e)))?;
+
+    let mut summary = RetrySummary::default();
+    let mut jobs = Vec::new();
+    for (record, id, url, file_name) in failed {
+        // Claimed here so a game already downloading (or a second retry) is left alone
+        match maintenance::track_download(id) {
+            Ok(true) => {}
+            Ok(false) => continue,
+            Err(e) => {
+                summary.failed.push((id, e));
+                continue;
+            }
+        }
+        // The row is only changed if this succeeds, so on an error it's still failed
+        match history::requeue_record(record) {
+            Ok(true) => {}
+            Ok(false) => {
+                queue::untrack(id);
+                continue;
+            }
+            Err(e) => {
+                warn!("Failed to requeue download {}: {}", record, e);
+                queue::untrack(id);
+                summary.failed.push((id, e));
+                continue;
+            }
+        }
+        jobs.push(DownloadJob {
+            id,
+            url,
+            file_name,
+            save_as: None,
+            download_dir: None,
+            zip_dir: None,
+            extract_dir: None,
+            extract: None,
+            retry_of: Some(record),
+            force: false,
+            flat: false,
+        });
+    }
 
-        // Notify UI
-        window
-            .emit("download-complete", DownloadCompletePayload { id })
-            .map_err(|e| format!("Emit failed: {}", e))?;
+    summary.requeued = jobs.len();
+    info!("Requeued {} failed downloads", summary.requeued);
+    if !jobs.is_empty() {
+        std::thread::spawn(move || {
+            let results = run_in_order(jobs, queue::max_concurrent(), |job| {
+                let id = job.id;
+                let result = download_tracked(&window, job);
+                queue::untrack(id);
+                result
+            });
+            let failed = results.iter().filter(|r| r.is_err()).count();
+            info!("Retried {} downloads, {} failed again", results.len(), failed);
+        });
+    }
+    Ok(summary)
+}
 
-        Ok(format!(
-            "Downloaded to {:?} and extracted to {:?}",
-            zip_path, extract_dir
-        ))
+// How long cancel_all_downloads waits for workers to wind down
+const CANCEL_WAIT: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// Stops every queued and running download: partial archives are deleted and each
+/// emits `download-cancelled`. Returns how many were stopped.
+#[tauri::command]
+pub async fn cancel_all_downloads() -> Result<usize, AppError> {
+    let cancel_task = task::spawn_blocking(move || {
+        let ids = queue::cancel_all();
+        if !queue::wait_untracked(&ids, CANCEL_WAIT) {
+            warn!("Some downloads were still stopping after {:?}", CANCEL_WAIT);
+        }
+        ids.len()
     });
 
-    download_task.await.map_err(|e| e.to_string())?
+    cancel_task.await.map_err(|e| AppError::internal(e.to_string()))
 }
 
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+    use std::time::Instant;
+
+    use super::*;
+
+    #[test]
+    fn chunk_count_follows_size_bands() {
+        let (single, two) = (DEFAULT_SINGLE_STREAM_BELOW_BYTES, DEFAULT_TWO_CHUNKS_BELOW_BYTES);
+
+        assert_eq!(chunks_for_size(256 * 1024, single, two, 4), 1);
+        assert_eq!(chunks_for_size(single, single, two, 4), 2);
+        assert_eq!(chunks_for_size(two, single, two, 4), 4);
+        assert_eq!(chunks_for_size(two, single, two, 8), 8);
+        // A lower max caps the middle band too
+        assert_eq!(chunks_for_size(single, single, two, 1), 1);
+    }
+
+    #[test]
+    fn place_flat_numbers_colliding_names() {
+        let root = std::env::temp_dir().join(format!("rusty-roms-flat-{}", std::process::id()));
+        let (src, dest) = (root.join(".Tetris.zip.extracting"), root.join("roms"));
+        fs::create_dir_all(&src).unwrap();
+        fs::create_dir_all(&dest).unwrap();
+        fs::write(dest.join("Tetris.gb"), b"old").unwrap();
+        fs::write(src.join("Tetris.gb"), b"new").unwrap();
+        fs::write(src.join("README"), b"readme").unwrap();
+
+        let mut placed = place_flat(&src, &dest).unwrap();
+        placed.sort();
+
+        assert_eq!(placed, vec![dest.join("README"), dest.join("Tetris (1).gb")]);
+        assert_eq!(fs::read(dest.join("Tetris.gb")).unwrap(), b"old");
+        assert_eq!(fs::read(dest.join("Tetris (1).gb")).unwrap(), b"new");
+        assert!(!src.exists());
+        assert_eq!(numbered_name("README", 2), "README (2)");
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn reads_entries_from_the_central_directory() {
+        use zip::write::SimpleFileOptions;
+
+        let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        let options = SimpleFileOptions::default();
+        writer.add_directory("Disc 1/", options).unwrap();
+        writer.start_file("Disc 1/game.cue", options).unwrap();
+        writer.write_all(b"FILE game.bin BINARY").unwrap();
+        writer.start_file("Disc 1/game.bin", options).unwrap();
+        writer.write_all(&[0u8; 4096]).unwrap();
+        writer.set_comment("trailing comment");
+        let archive = writer.finish().unwrap().into_inner();
+
+        // Only the last few bytes, as list_archive_contents would fetch them
+        let total = archive.len() as u64;
+        let tail_start = archive.len() - 200;
+        let (offset, size) = central_directory_location(&archive[tail_start..], total).unwrap();
+        let entries = parse_central_directory(&archive[offset as usize..(offset + size) as usize]).unwrap();
+
+        let listed: Vec<_> = entries.iter().map(|e| (e.name.as_str(), e.size, e.is_dir)).collect();
+        assert_eq!(
+            listed,
+            vec![
+                ("Disc 1/", 0, true),
+                ("Disc 1/game.cue", 20, false),
+                ("Disc 1/game.bin", 4096, false),
+            ]
+        );
+        assert!(central_directory_location(b"not a zip at all, just text", 27).is_err());
+    }
+
+    #[test]
+    fn run_in_order_keeps_item_order() {
+        let started = Mutex::new(Vec::new());
+        let results = run_in_order((0..20).collect(), 3, |n: u32| {
+            started.lock().unwrap().push(n);
+            n * 2
+        });
+
+        assert_eq!(results, (0..20).map(|n| n * 2).collect::<Vec<_>>());
+        // One worker takes them strictly in order
+        started.lock().unwrap().clear();
+        run_in_order((0..5).collect(), 1, |n: u32| started.lock().unwrap().push(n));
+        assert_eq!(*started.lock().unwrap(), vec![0, 1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn checksums_come_from_the_archive_headers() {
+        use zip::write::SimpleFileOptions;
+
+        let root = std::env::temp_dir().join(format!("rusty-roms-verify-{}", std::process::id()));
+        fs::create_dir_all(&root).unwrap();
+        let archive_path = root.join("Tetris.zip");
+
+        let mut writer = zip::ZipWriter::new(File::create(&archive_path).unwrap());
+        let options = SimpleFileOptions::default();
+        writer.add_directory("Tetris/", options).unwrap();
+        writer.start_file("Tetris/Tetris.gb", options).unwrap();
+        writer.write_all(b"tetris rom").unwrap();
+        writer.finish().unwrap();
+
+        let checksums = archive_checksums(&archive_path).unwrap();
+        let expected = format!("{:08x}", crc32fast::hash(b"tetris rom"));
+        assert_eq!(checksums.len(), 1);
+        assert_eq!(checksums[0].0, "Tetris/Tetris.gb");
+        assert_eq!(format!("{:08x}", checksums[0].1), expected);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    // cargo test --release -- --ignored --nocapture buffer_benchmark
+    #[test]
+    #[ignore]
+    fn buffer_benchmark() {
+        const PAYLOAD: usize = 256 * 1024 * 1024;
+        let payload = vec![7u8; PAYLOAD];
+        let path = std::env::temp_dir().join("rusty-roms-buffer-benchmark.bin");
+
+        for kb in [8, 32, 256, MAX_DOWNLOAD_BUFFER_KB as usize] {
+            let mut source = Cursor::new(&payload);
+            let mut file = File::create(&path).unwrap();
+            let mut buffer = vec![0u8; kb * 1024];
+
+            // Same read/write_all shape as the download loops
+            let start = Instant::now();
+            loop {
+                let n = source.read(&mut buffer).unwrap();
+                if n == 0 {
+                    break;
+                }
+                file.write_all(&buffer[..n]).unwrap();
+            }
+            file.sync_all().unwrap();
+            let elapsed = start.elapsed();
+
+            println!(
+                "{:>4} KiB buffer: {:.0} MB/s",
+                kb,
+                PAYLOAD as f64 / 1_000_000.0 / elapsed.as_secs_f64()
+            );
+        }
+
+        let _ = fs::remove_file(&path);
+    }
+}
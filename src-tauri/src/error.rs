@@ -0,0 +1,76 @@
+use std::fmt;
+
+use serde::Serialize;
+
+/// Error returned by every command. Serialized as `{ kind, message }` so the UI can
+/// tell a network failure from a full disk, and still show `message` as-is.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum AppError {
+    Network { message: String },
+    Io { message: String },
+    Db { message: String },
+    NotFound { message: String },
+    // The download link is gone upstream (404/410); refreshing the library fixes it
+    Stale { message: String },
+    InvalidInput { message: String },
+    // Stopped on request; nothing to retry unless the user asks again
+    Cancelled { message: String },
+    // Bugs and broken invariants: a panicked worker, a window that's gone
+    Internal { message: String },
+}
+
+impl AppError {
+    pub fn network(message: impl Into<String>) -> Self {
+        AppError::Network { message: message.into() }
+    }
+
+    pub fn io(message: impl Into<String>) -> Self {
+        AppError::Io { message: message.into() }
+    }
+
+    pub fn db(message: impl Into<String>) -> Self {
+        AppError::Db { message: message.into() }
+    }
+
+    pub fn not_found(message: impl Into<String>) -> Self {
+        AppError::NotFound { message: message.into() }
+    }
+
+    pub fn stale(message: impl Into<String>) -> Self {
+        AppError::Stale { message: message.into() }
+    }
+
+    pub fn invalid_input(message: impl Into<String>) -> Self {
+        AppError::InvalidInput { message: message.into() }
+    }
+
+    pub fn cancelled(message: impl Into<String>) -> Self {
+        AppError::Cancelled { message: message.into() }
+    }
+
+    pub fn internal(message: impl Into<String>) -> Self {
+        AppError::Internal { message: message.into() }
+    }
+
+    pub fn message(&self) -> &str {
+        match self {
+            AppError::Network { message }
+            | AppError::Io { message }
+            | AppError::Db { message }
+            | AppError::NotFound { message }
+            | AppError::Stale { message }
+            | AppError::InvalidInput { message }
+            | AppError::Cancelled { message }
+            | AppError::Internal { message } => message,
+        }
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.message())
+    }
+}
+
+impl std::error::Error for AppError {}
@@ -0,0 +1,72 @@
+use rusqlite::{params, Connection, OptionalExtension};
+use tauri::command;
+
+use crate::error::AppError;
+use crate::history::now_unix;
+use crate::db;
+use crate::query::{game_from_row, Game, GAME_COLUMNS, GAME_TABLES};
+
+fn open_db() -> Result<Connection, AppError> {
+    db::open().map_err(|e| AppError::db(format!("Failed to open DB: {}", e)))
+}
+
+// Favorites are stored by (name, console), since a full rebuild reuses game ids
+#[command]
+pub fn add_favorite(id: u32) -> Result<(), AppError> {
+    let conn = open_db()?;
+    let added = conn
+        .execute(
+            "INSERT OR IGNORE INTO favorites (game_name, game_console, added_at)
+             SELECT name, console, ?2 FROM games WHERE id = ?1",
+            params![id as i64, now_unix()],
+        )
+        .map_err(|e| AppError::db(format!("Failed to add favorite: {}", e)))?;
+    if added == 0 && get_game_key(&conn, id)?.is_none() {
+        return Err(AppError::not_found(format!("No game with id {}", id)));
+    }
+    Ok(())
+}
+
+#[command]
+pub fn remove_favorite(id: u32) -> Result<(), AppError> {
+    let conn = open_db()?;
+    if let Some((name, console)) = get_game_key(&conn, id)? {
+        conn.execute(
+            "DELETE FROM favorites WHERE game_name = ?1 AND game_console = ?2",
+            params![name, console],
+        )
+        .map_err(|e| AppError::db(format!("Failed to remove favorite: {}", e)))?;
+    }
+    Ok(())
+}
+
+fn get_game_key(conn: &Connection, id: u32) -> Result<Option<(String, String)>, AppError> {
+    conn.query_row(
+        "SELECT name, console FROM games WHERE id = ?1",
+        [id as i64],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )
+    .optional()
+    .map_err(|e| AppError::db(format!("Failed to read game {}: {}", id, e)))
+}
+
+/// Favorited games, most recently added first.
+#[command]
+pub fn list_favorites() -> Result<Vec<Game>, AppError> {
+    let conn = open_db()?;
+
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {} FROM {} WHERE favorites.added_at IS NOT NULL ORDER BY favorites.added_at DESC",
+            GAME_COLUMNS, GAME_TABLES
+        ))
+        .map_err(|e| AppError::db(format!("Failed to prepare query: {}", e)))?;
+
+    let games = stmt
+        .query_map([], game_from_row)
+        .map_err(|e| AppError::db(format!("Query execution failed: {}", e)))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| AppError::db(format!("Row error: {}", e)))?;
+
+    Ok(games)
+}
@@ -0,0 +1,215 @@
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::db;
+use crate::error::AppError;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DownloadState {
+    Queued,
+    Downloading,
+    Extracting,
+    Done,
+    Failed,
+    Cancelled,
+    Removed,
+}
+
+impl DownloadState {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            DownloadState::Queued => "queued",
+            DownloadState::Downloading => "downloading",
+            DownloadState::Extracting => "extracting",
+            DownloadState::Done => "done",
+            DownloadState::Failed => "failed",
+            DownloadState::Cancelled => "cancelled",
+            DownloadState::Removed => "removed",
+        }
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct DownloadRecord {
+    pub id: i64,
+    pub game_id: Option<i64>, // None once the game is no longer in the catalog
+    pub game_name: Option<String>,
+    pub state: String,
+    pub started_at: i64,
+    pub finished_at: Option<i64>,
+    pub error: Option<String>,
+    pub bytes_downloaded: i64,
+}
+
+pub fn now_unix() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+fn open_db() -> Result<Connection, AppError> {
+    db::open().map_err(|e| AppError::db(format!("Failed to open DB: {}", e)))
+}
+
+/// Opens a history row for a new download attempt and returns its id. The game's name
+/// and console are stored with it, since a full rebuild reuses ids for other games.
+pub fn start_record(game_id: u32) -> Result<i64, AppError> {
+    let conn = open_db()?;
+    let (name, console): (Option<String>, Option<String>) = conn
+        .query_row(
+            "SELECT name, console FROM games WHERE id = ?1",
+            [game_id as i64],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()
+        .map_err(|e| AppError::db(format!("Failed to read game {}: {}", game_id, e)))?
+        .unzip();
+    conn.execute(
+        "INSERT INTO downloads (game_id, game_name, console, state, started_at) VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![game_id as i64, name, console, DownloadState::Queued.as_str(), now_unix()],
+    )
+    .map_err(|e| AppError::db(format!("Failed to record download: {}", e)))?;
+    Ok(conn.last_insert_rowid())
+}
+
+pub fn set_state(record: i64, state: DownloadState) -> Result<(), AppError> {
+    let conn = open_db()?;
+    db::retry_busy(|| {
+        conn.execute(
+            "UPDATE downloads SET state = ?1 WHERE id = ?2",
+            params![state.as_str(), record],
+        )
+    })
+    .map_err(|e| AppError::db(format!("Failed to update download state: {}", e)))?;
+    Ok(())
+}
+
+pub fn set_bytes(record: i64, bytes: u64) -> Result<(), AppError> {
+    let conn = open_db()?;
+    db::retry_busy(|| {
+        conn.execute(
+            "UPDATE downloads SET bytes_downloaded = ?1 WHERE id = ?2",
+            params![bytes as i64, record],
+        )
+    })
+    .map_err(|e| AppError::db(format!("Failed to update downloaded bytes: {}", e)))?;
+    Ok(())
+}
+
+/// Records where a download's files ended up.
+pub fn set_path(record: i64, path: &Path) -> Result<(), AppError> {
+    let conn = open_db()?;
+    conn.execute(
+        "UPDATE downloads SET path = ?1 WHERE id = ?2",
+        params![path.to_string_lossy(), record],
+    )
+    .map_err(|e| AppError::db(format!("Failed to update download path: {}", e)))?;
+    Ok(())
+}
+
+/// Records the `ETag` / `Last-Modified` the archive was served with, for `check_for_updates`.
+pub fn set_validators(record: i64, etag: Option<&str>, last_modified: Option<&str>) -> Result<(), AppError> {
+    let conn = open_db()?;
+    conn.execute(
+        "UPDATE downloads SET etag = ?1, last_modified = ?2 WHERE id = ?3",
+        params![etag, last_modified, record],
+    )
+    .map_err(|e| AppError::db(format!("Failed to update download validators: {}", e)))?;
+    Ok(())
+}
+
+/// Path recorded by the game's most recent finished download, if any.
+pub fn last_path(name: &str, console: &str) -> Result<Option<PathBuf>, AppError> {
+    let conn = open_db()?;
+    let path: Option<String> = conn
+        .query_row(
+            "SELECT path FROM downloads
+             WHERE game_name = ?1 AND console = ?2 AND state = ?3 AND path IS NOT NULL
+             ORDER BY id DESC LIMIT 1",
+            params![name, console, DownloadState::Done.as_str()],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| AppError::db(format!("Failed to read download path: {}", e)))?;
+    Ok(path.map(PathBuf::from))
+}
+
+/// Puts a failed record back to queued for another attempt, clearing its error.
+/// `false` if it wasn't failed (anymore), e.g. another retry got to it first.
+pub fn requeue_record(record: i64) -> Result<bool, AppError> {
+    let conn = open_db()?;
+    let updated = db::retry_busy(|| {
+        conn.execute(
+            "UPDATE downloads SET state = ?1, error = NULL, finished_at = NULL, bytes_downloaded = 0
+             WHERE id = ?2 AND state = ?3",
+            params![DownloadState::Queued.as_str(), record, DownloadState::Failed.as_str()],
+        )
+    })
+    .map_err(|e| AppError::db(format!("Failed to requeue download record: {}", e)))?;
+    Ok(updated > 0)
+}
+
+/// Moves a record to a terminal state (done/failed/cancelled) and stamps `finished_at`.
+pub fn finish_record(record: i64, state: DownloadState, error: Option<&str>) -> Result<(), AppError> {
+    let conn = open_db()?;
+    db::retry_busy(|| {
+        conn.execute(
+            "UPDATE downloads SET state = ?1, error = ?2, finished_at = ?3 WHERE id = ?4",
+            params![state.as_str(), error, now_unix(), record],
+        )
+    })
+    .map_err(|e| AppError::db(format!("Failed to finish download record: {}", e)))?;
+    Ok(())
+}
+
+/// Flags a game's completed downloads as removed once its files are deleted.
+pub fn mark_removed(name: &str, console: &str) -> Result<(), AppError> {
+    let conn = open_db()?;
+    conn.execute(
+        "UPDATE downloads SET state = ?1 WHERE game_name = ?2 AND console = ?3 AND state = ?4",
+        params![
+            DownloadState::Removed.as_str(),
+            name,
+            console,
+            DownloadState::Done.as_str()
+        ],
+    )
+    .map_err(|e| AppError::db(format!("Failed to update download state: {}", e)))?;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_download_history() -> Result<Vec<DownloadRecord>, AppError> {
+    let conn = open_db()?;
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT d.id, g.id, d.game_name, d.state, d.started_at, d.finished_at, d.error, d.bytes_downloaded
+             FROM downloads d
+             LEFT JOIN games g ON g.name = d.game_name AND g.console = d.console
+             ORDER BY d.id DESC",
+        )
+        .map_err(|e| AppError::db(format!("Failed to prepare query: {}", e)))?;
+
+    let records = stmt
+        .query_map([], |row| {
+            Ok(DownloadRecord {
+                id: row.get(0)?,
+                game_id: row.get(1)?,
+                game_name: row.get(2)?,
+                state: row.get(3)?,
+                started_at: row.get(4)?,
+                finished_at: row.get(5)?,
+                error: row.get(6)?,
+                bytes_downloaded: row.get(7)?,
+            })
+        })
+        .map_err(|e| AppError::db(format!("Query execution failed: {}", e)))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| AppError::db(format!("Row error: {}", e)))?;
+
+    Ok(records)
+}
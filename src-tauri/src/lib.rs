@@ -1,33 +1,167 @@
 mod query;
-mod setup;
 mod data;
-use data::{scrape, setup, console_fill, remove_old_db};
+mod db;
+use data::{scrape, setup, console_fill, drop_games};
 mod download;
+mod error;
 mod status;
 mod settings;
 mod start;
+mod sources;
+mod history;
+mod net;
+mod queue;
+mod library;
+mod favorites;
+mod maintenance;
+mod catalog;
+mod checksum;
+mod paths;
+mod logging;
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_dialog::init())
-        // .setup(|_app| {
-        //     setup::temp_dir();
-        //     std::thread::spawn(|| {
-        //         run_startup_tasks();
-        //     });
-        //     Ok(())
-        // })
+        .setup(|_app| {
+            logging::init();
+
+            // Older builds kept their data in ~/.roms-tauri
+            paths::migrate_legacy_data_dir();
+
+            // Bring an existing DB up to the current schema without wiping it
+            if let Err(e) = data::setup() {
+                tracing::error!("DB setup failed: {}", e);
+            }
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             query::search_games,
+            query::stream_search,
+            query::list_consoles,
+            query::recently_added,
+            query::list_regions,
+            query::get_game,
+            query::console_stats,
             download::download_file,
+            download::download_games,
+            download::cancel_all_downloads,
+            download::retry_failed_downloads,
+            download::estimate_download,
+            download::check_for_updates,
+            download::list_archive_contents,
+            download::console_total_size,
+            download::validate_link,
+            queue::get_active_downloads,
+            queue::get_speed_samples,
+            queue::get_queue_state,
+            queue::pause_queue,
+            queue::resume_queue,
+            queue::get_download_queue,
+            queue::move_in_queue,
+            queue::prioritize_download,
+            queue::dequeue_download,
+            library::scan_downloads,
+            library::delete_download,
+            library::get_installed_size,
+            library::enforce_library_quota,
+            library::reconcile_library,
+            library::cleanup_partial_downloads,
+            library::verify_existing,
+            checksum::compute_file_hash,
             status::network_check,
             settings::get_download_dir,
             settings::set_download_dir,
+            settings::get_download_dir_status,
+            settings::get_staging_dir,
+            settings::set_staging_dir,
+            settings::get_zip_dir,
+            settings::set_zip_dir,
+            settings::get_extract_dir,
+            settings::set_extract_dir,
+            settings::get_library_dir,
+            settings::set_library_dir,
             settings::pick_download_dir,
             settings::clear_download_dir,
-            start::run_startup_tasks
+            settings::get_delete_zip_after_extract,
+            settings::set_delete_zip_after_extract,
+            settings::get_verify_before_delete,
+            settings::set_verify_before_delete,
+            settings::get_auto_extract,
+            settings::set_auto_extract,
+            settings::get_organize_by_console,
+            settings::set_organize_by_console,
+            settings::get_compact_after_scrape,
+            settings::set_compact_after_scrape,
+            settings::get_proxy,
+            settings::set_proxy,
+            settings::get_mirror,
+            settings::set_mirror,
+            settings::get_request_timeout,
+            settings::set_request_timeout,
+            settings::get_user_agent,
+            settings::set_user_agent,
+            settings::get_refresh_stale_links,
+            settings::set_refresh_stale_links,
+            settings::get_max_concurrent_downloads,
+            settings::set_max_concurrent_downloads,
+            settings::get_max_connections,
+            settings::set_max_connections,
+            settings::get_console_dirs,
+            settings::set_console_dir,
+            settings::get_scrape_retries,
+            settings::set_scrape_retries,
+            settings::get_scrape_retry_backoff_ms,
+            settings::set_scrape_retry_backoff_ms,
+            settings::get_scrape_delay_ms,
+            settings::set_scrape_delay_ms,
+            settings::get_download_mode,
+            settings::set_download_mode,
+            settings::get_download_buffer_kb,
+            settings::set_download_buffer_kb,
+            settings::get_chunk_settings,
+            settings::set_chunk_settings,
+            settings::get_keep_partial_extractions,
+            settings::set_keep_partial_extractions,
+            start::run_startup_tasks,
+            start::cancel_startup,
+            start::last_scrape_time,
+            settings::get_scrape_on_startup,
+            settings::set_scrape_on_startup,
+            settings::get_cleanup_partials_on_start,
+            settings::set_cleanup_partials_on_start,
+            settings::get_scrape_interval_hours,
+            settings::set_scrape_interval_hours,
+            sources::list_console_sources,
+            sources::console_scrape_status,
+            sources::add_console,
+            sources::remove_console,
+            sources::set_console_headers,
+            sources::get_console_headers,
+            sources::refresh_console,
+            sources::refresh_link,
+            sources::browse_console,
+            sources::list_scrape_filters,
+            sources::add_scrape_filter,
+            sources::remove_scrape_filter,
+            sources::clear_scrape_filters,
+            history::get_download_history,
+            favorites::add_favorite,
+            favorites::remove_favorite,
+            favorites::list_favorites,
+            maintenance::compact_database,
+            maintenance::clean_database,
+            maintenance::backup_database,
+            maintenance::restore_database,
+            maintenance::check_database,
+            catalog::export_catalog,
+            catalog::import_owned_list,
+            logging::get_recent_logs,
+            settings::get_log_level,
+            settings::set_log_level,
+            settings::get_max_library_size_bytes,
+            settings::set_max_library_size_bytes
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -0,0 +1,714 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use rusqlite::Connection;
+use serde::Serialize;
+use tauri::{Emitter, Window};
+use tokio::task;
+use tracing::{info, warn};
+
+use crate::checksum::{compute_hash_with_progress, HashAlgorithm};
+use crate::data::{file_extension, strip_extension};
+use crate::download::{
+    archive_checksums, archive_file_name, extract_dir_for, resolve_download_dir, resolve_download_targets, safe_join,
+    sanitize_file_name, staging_dir,
+};
+use crate::error::AppError;
+use crate::history;
+use crate::queue;
+use crate::db;
+use crate::query::{game_from_row, get_game, normalize_name, Game, GAME_COLUMNS, GAME_TABLES};
+use crate::settings::read_u64_setting;
+
+#[derive(Serialize, Clone, Debug)]
+struct DownloadRemovedPayload {
+    id: u32,
+}
+
+#[derive(Serialize, Clone, Debug)]
+struct ScanProgressPayload {
+    scanned: usize,
+    total: usize,
+}
+
+// Emit scan progress every this many entries
+const SCAN_EMIT_EVERY: usize = 100;
+
+fn open_db() -> Result<Connection, AppError> {
+    db::open().map_err(|e| AppError::db(format!("Failed to open DB: {}", e)))
+}
+
+/// The global download dir, the zip and extract dirs, plus every per-console override.
+fn download_roots(conn: &Connection) -> Result<Vec<PathBuf>, AppError> {
+    let (zip_root, extract_root) = resolve_download_targets(None, None, None, None)?;
+    let mut roots = vec![resolve_download_dir(None, None)?];
+    for dir in [zip_root, extract_root] {
+        if !roots.contains(&dir) {
+            roots.push(dir);
+        }
+    }
+
+    let mut stmt = conn
+        .prepare("SELECT path FROM console_dirs")
+        .map_err(|e| AppError::db(format!("Failed to prepare query: {}", e)))?;
+    let console_dirs = stmt
+        .query_map([], |row| row.get::<_, String>(0))
+        .map_err(|e| AppError::db(format!("Query execution failed: {}", e)))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| AppError::db(format!("Row error: {}", e)))?;
+
+    for dir in console_dirs {
+        let dir = PathBuf::from(dir);
+        if !roots.contains(&dir) {
+            roots.push(dir);
+        }
+    }
+    Ok(roots)
+}
+
+/// File stems and folder names up to `depth` levels below `dir`
+/// (root/<game> and root/<console>/<game>).
+fn collect_entry_names(dir: &Path, depth: usize, names: &mut Vec<String>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Some(name) = path.file_name().and_then(|s| s.to_str()) else {
+            continue;
+        };
+        // Hidden entries include unfinished `.<name>.partial` extractions
+        if name.starts_with('.') {
+            continue;
+        }
+
+        if path.is_dir() {
+            // Folder names keep their dots: "Super Mario Bros. (USA)"
+            names.push(name.to_string());
+            if depth > 1 {
+                collect_entry_names(&path, depth - 1, names);
+            }
+        } else {
+            names.push(strip_extension(name).to_string());
+        }
+    }
+}
+
+/// Marks games whose archive or extracted folder already sits in a download dir.
+/// Returns the ids that were newly flagged.
+#[tauri::command]
+pub async fn scan_downloads(window: Window) -> Result<Vec<u32>, AppError> {
+    let scan_task = task::spawn_blocking(move || -> Result<Vec<u32>, AppError> {
+        let conn = open_db()?;
+
+        let mut names = Vec::new();
+        for root in download_roots(&conn)? {
+            collect_entry_names(&root, 2, &mut names);
+        }
+
+        let mut by_name: HashMap<String, Vec<u32>> = HashMap::new();
+        {
+            let mut stmt = conn
+                .prepare("SELECT id, name FROM games WHERE is_downloaded = 0")
+                .map_err(|e| AppError::db(format!("Failed to prepare query: {}", e)))?;
+            let rows = stmt
+                .query_map([], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?)))
+                .map_err(|e| AppError::db(format!("Query execution failed: {}", e)))?;
+            for row in rows {
+                let (id, name) = row.map_err(|e| AppError::db(format!("Row error: {}", e)))?;
+                by_name.entry(normalize_name(&name)).or_default().push(id as u32);
+            }
+        }
+
+        let total = names.len();
+        let mut found = Vec::new();
+        for (i, name) in names.iter().enumerate() {
+            // remove() so a game present in two places is only counted once
+            if let Some(ids) = by_name.remove(&normalize_name(name)) {
+                found.extend(ids);
+            }
+            if (i + 1) % SCAN_EMIT_EVERY == 0 || i + 1 == total {
+                let _ = window.emit(
+                    "scan-progress",
+                    ScanProgressPayload {
+                        scanned: i + 1,
+                        total,
+                    },
+                );
+            }
+        }
+
+        let tx = conn
+            .unchecked_transaction()
+            .map_err(|e| AppError::db(format!("Failed to start transaction: {}", e)))?;
+        for id in &found {
+            tx.execute("UPDATE games SET is_downloaded = 1 WHERE id = ?1", [*id as i64])
+                .map_err(|e| AppError::db(format!("Failed to update is_downloaded: {}", e)))?;
+        }
+        tx.commit().map_err(|e| AppError::db(format!("Failed to commit: {}", e)))?;
+
+        Ok(found)
+    });
+
+    scan_task.await.map_err(|e| AppError::internal(e.to_string()))?
+}
+
+/// Existing files and folders belonging to a game: its archive and extracted folder,
+/// wherever run_download may have put them, whatever organize_by_console was at the time.
+/// Names go through safe_join so a malformed one can't point outside the download dir.
+fn game_file_targets(game: &Game) -> Result<Vec<PathBuf>, AppError> {
+    let (zip_root, extract_root) = resolve_download_targets(None, None, None, Some(&game.console))?;
+
+    let archive = archive_file_name(&game.name, &game.dl_link);
+    let mut targets = vec![safe_join(&zip_root, &archive)?];
+    for by_console in [true, false] {
+        let dir = extract_dir_for(Path::new(""), &archive, Some(&game.console), by_console);
+        targets.push(safe_join(&extract_root, &dir.to_string_lossy())?);
+    }
+
+    // The download dir may have changed since, so the recorded location counts too, but
+    // only inside a folder downloads go to: the history row may be stale or hand-edited
+    if let Some(recorded) = history::last_path(&game.name, &game.console)? {
+        let mut roots = download_roots(&open_db()?)?;
+        roots.extend([zip_root.clone(), extract_root.clone()]);
+        let known = roots.iter().any(|root| recorded != *root && recorded.starts_with(root));
+        if !known {
+            warn!("Ignoring recorded path {:?} for {}: not in a download folder", recorded, game.name);
+        } else if !targets.contains(&recorded) {
+            targets.push(recorded);
+        }
+    }
+
+    targets.retain(|t| *t != zip_root && *t != extract_root && t.exists());
+    Ok(targets)
+}
+
+fn path_size(path: &Path) -> u64 {
+    let Ok(meta) = fs::symlink_metadata(path) else {
+        return 0;
+    };
+    if !meta.is_dir() {
+        return meta.len();
+    }
+    fs::read_dir(path)
+        .map(|entries| entries.flatten().map(|e| path_size(&e.path())).sum())
+        .unwrap_or(0)
+}
+
+/// Bytes a game's files take up on disk.
+pub(crate) fn game_disk_size(game: &Game) -> Result<u64, AppError> {
+    Ok(game_file_targets(game)?.iter().map(|t| path_size(t)).sum())
+}
+
+/// Real bytes a downloaded game takes up (archive plus extracted files), which can be
+/// far more than the scraped zip size once RVZ/nkit images or multi-file sets are unpacked.
+#[tauri::command]
+pub async fn get_installed_size(id: u32) -> Result<u64, AppError> {
+    let size_task = task::spawn_blocking(move || {
+        let game = get_game(id as i64)?.ok_or_else(|| AppError::not_found(format!("No game with id {}", id)))?;
+        if !game.is_downloaded {
+            return Err(AppError::invalid_input(format!("{} is not downloaded", game.name)));
+        }
+
+        let targets = game_file_targets(&game)?;
+        if targets.is_empty() {
+            return Err(AppError::not_found(format!("Files for {} are missing", game.name)));
+        }
+        Ok(targets.iter().map(|t| path_size(t)).sum())
+    });
+
+    size_task.await.map_err(|e| AppError::internal(e.to_string()))?
+}
+
+/// Removes a game's archive and extracted folder from its download dir.
+pub(crate) fn remove_game_files(game: &Game) -> Result<(), AppError> {
+    for target in game_file_targets(game)? {
+        let removed = if target.is_dir() {
+            fs::remove_dir_all(&target)
+        } else {
+            fs::remove_file(&target)
+        };
+        removed.map_err(|e| AppError::io(format!("Failed to remove {:?}: {}", target, e)))?;
+    }
+
+    Ok(())
+}
+
+/// Deletes a game's files, flags it not downloaded and tells the UI.
+fn remove_download(window: &Window, game: &Game) -> Result<(), AppError> {
+    let id = game.id as u32;
+    remove_game_files(game)?;
+
+    let conn = open_db()?;
+    conn.execute("UPDATE games SET is_downloaded = 0 WHERE id = ?1", [id as i64])
+        .map_err(|e| AppError::db(format!("Failed to update is_downloaded: {}", e)))?;
+    history::mark_removed(&game.name, &game.console)?;
+
+    let _ = window.emit("download-removed", DownloadRemovedPayload { id });
+    Ok(())
+}
+
+/// Deletes a game's archive and extracted folder and flips it back to not downloaded.
+/// Refused while the game is queued or downloading: the worker would write under it and
+/// mark the game downloaded again when it finishes.
+#[tauri::command]
+pub fn delete_download(id: u32, window: Window) -> Result<(), AppError> {
+    if queue::is_tracked(id) {
+        return Err(AppError::invalid_input(format!(
+            "Game {} is queued or downloading; cancel it before deleting",
+            id
+        )));
+    }
+    let game = get_game(id as i64)?.ok_or_else(|| AppError::not_found(format!("No game with id {}", id)))?;
+    remove_download(&window, &game)
+}
+
+#[derive(Serialize, Clone, Debug)]
+struct VerifyProgressPayload {
+    id: u32,
+    index: usize, // entry being checked, from 1
+    total: usize,
+    entry: String,
+    bytes_hashed: u64,
+    entry_size: u64,
+}
+
+// An entry's hashing progress is emitted at most once per this many bytes
+const VERIFY_EMIT_BYTES: u64 = 16 * 1024 * 1024;
+
+#[derive(Serialize, Clone, Debug)]
+pub struct VerifyReport {
+    pub checked: usize,
+    pub mismatched: Vec<String>, // entries missing or not matching the archive's CRC-32
+    pub archive: String,         // the kept archive the checksums came from
+}
+
+/// Where a game's entries were extracted to: whichever candidate folder holds most of them.
+fn extracted_base(game: &Game, archive_name: &str, extract_root: &Path, entries: &[(String, u32)]) -> Result<PathBuf, AppError> {
+    let mut candidates = Vec::new();
+    // A lone file or folder placed flat is recorded itself, so its parent is the base
+    if let Some(recorded) = history::last_path(&game.name, &game.console)? {
+        if let Some(parent) = recorded.parent() {
+            candidates.push(parent.to_path_buf());
+        }
+        candidates.insert(0, recorded);
+    }
+    for by_console in [true, false] {
+        candidates.push(extract_dir_for(extract_root, archive_name, Some(&game.console), by_console));
+    }
+
+    let present = |base: &Path| {
+        entries
+            .iter()
+            .filter(|(name, _)| safe_join(base, name).is_ok_and(|p| p.is_file()))
+            .count()
+    };
+    // Reversed so a tie goes to the earlier candidate
+    let best = candidates
+        .iter()
+        .rev()
+        .max_by_key(|base| present(base))
+        .cloned()
+        .unwrap_or_else(|| extract_root.to_path_buf());
+    Ok(best)
+}
+
+/// Checks a downloaded game's extracted files against the CRC-32s its kept archive
+/// recorded, without touching the network, e.g. to catch bit-rot or a bad manual copy.
+/// Emits `verify-progress` as each file is hashed. Fails with `notFound` when there's no
+/// reference checksum, i.e. the archive wasn't kept.
+#[tauri::command]
+pub async fn verify_existing(id: u32, window: Window) -> Result<VerifyReport, AppError> {
+    let verify_task = task::spawn_blocking(move || -> Result<VerifyReport, AppError> {
+        let game = get_game(id as i64)?.ok_or_else(|| AppError::not_found(format!("No game with id {}", id)))?;
+        if !game.is_downloaded {
+            return Err(AppError::invalid_input(format!("{} isn't downloaded", game.name)));
+        }
+
+        let (zip_root, extract_root) = resolve_download_targets(None, None, None, Some(&game.console))?;
+        let archive_name = archive_file_name(&game.name, &game.dl_link);
+        let archive = safe_join(&zip_root, &archive_name)?;
+        if !archive.is_file() {
+            return Err(AppError::not_found(format!(
+                "No reference checksum for {}: its archive wasn't kept",
+                game.name
+            )));
+        }
+        if history::last_path(&game.name, &game.console)?.as_deref() == Some(archive.as_path()) {
+            return Err(AppError::invalid_input(format!(
+                "{} was downloaded without extracting; there are no files to verify",
+                game.name
+            )));
+        }
+
+        let checksums = archive_checksums(&archive)?;
+        let base = extracted_base(&game, &archive_name, &extract_root, &checksums)?;
+
+        let mut mismatched = Vec::new();
+        for (i, (name, crc)) in checksums.iter().enumerate() {
+            let path = safe_join(&base, name)?;
+            let entry_size = fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+            let emit = |bytes_hashed: u64| {
+                let _ = window.emit(
+                    "verify-progress",
+                    VerifyProgressPayload {
+                        id,
+                        index: i + 1,
+                        total: checksums.len(),
+                        entry: name.clone(),
+                        bytes_hashed,
+                        entry_size,
+                    },
+                );
+            };
+            emit(0);
+
+            let mut last_emit = 0u64;
+            let matches = path.is_file()
+                && compute_hash_with_progress(&path, HashAlgorithm::Crc32, |hashed| {
+                    if hashed - last_emit >= VERIFY_EMIT_BYTES {
+                        last_emit = hashed;
+                        emit(hashed);
+                    }
+                })
+                .is_ok_and(|hash| hash == format!("{:08x}", crc));
+            if !matches {
+                mismatched.push(name.clone());
+            }
+        }
+
+        if mismatched.is_empty() {
+            info!("Verified {} files of {}", checksums.len(), game.name);
+        } else {
+            warn!("{} of {} files of {} failed verification", mismatched.len(), checksums.len(), game.name);
+        }
+        Ok(VerifyReport {
+            checked: checksums.len(),
+            mismatched,
+            archive: archive.to_string_lossy().to_string(),
+        })
+    });
+
+    verify_task.await.map_err(|e| AppError::internal(e.to_string()))?
+}
+
+#[derive(Serialize, Clone, Debug)]
+struct ReconcileProgressPayload {
+    checked: usize,
+    total: usize,
+}
+
+/// A file counts if it has bytes, a folder if it has anything in it.
+pub(crate) fn has_content(path: &Path) -> bool {
+    match fs::metadata(path) {
+        Ok(meta) if meta.is_dir() => fs::read_dir(path)
+            .map(|mut entries| entries.next().is_some())
+            .unwrap_or(false),
+        Ok(meta) => meta.len() > 0,
+        Err(_) => false,
+    }
+}
+
+/// Flips `is_downloaded` back for games whose files were moved or deleted outside
+/// the app, and returns them so the UI can offer a redownload.
+#[tauri::command]
+pub async fn reconcile_library(window: Window) -> Result<Vec<Game>, AppError> {
+    let reconcile_task = task::spawn_blocking(move || -> Result<Vec<Game>, AppError> {
+        let conn = open_db()?;
+        let mut stmt = conn
+            .prepare(&format!(
+                "SELECT {} FROM {} WHERE is_downloaded = 1",
+                GAME_COLUMNS, GAME_TABLES
+            ))
+            .map_err(|e| AppError::db(format!("Failed to prepare query: {}", e)))?;
+        let games = stmt
+            .query_map([], game_from_row)
+            .map_err(|e| AppError::db(format!("Query execution failed: {}", e)))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| AppError::db(format!("Row error: {}", e)))?;
+
+        let total = games.len();
+        let mut reset = Vec::new();
+        for (i, game) in games.into_iter().enumerate() {
+            // Downloads from before paths were recorded (or found by scan_downloads)
+            // fall back to wherever run_download would have put them
+            let present = match history::last_path(&game.name, &game.console)? {
+                Some(path) => has_content(&path),
+                None => game_file_targets(&game)?.iter().any(|t| has_content(t)),
+            };
+            if !present {
+                conn.execute("UPDATE games SET is_downloaded = 0 WHERE id = ?1", [game.id])
+                    .map_err(|e| AppError::db(format!("Failed to update is_downloaded: {}", e)))?;
+                history::mark_removed(&game.name, &game.console)?;
+                reset.push(Game {
+                    is_downloaded: false,
+                    ..game
+                });
+            }
+            if (i + 1) % SCAN_EMIT_EVERY == 0 || i + 1 == total {
+                let _ = window.emit(
+                    "reconcile-progress",
+                    ReconcileProgressPayload {
+                        checked: i + 1,
+                        total,
+                    },
+                );
+            }
+        }
+
+        Ok(reset)
+    });
+
+    reconcile_task.await.map_err(|e| AppError::internal(e.to_string()))?
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct RemovedGame {
+    pub id: i64,
+    pub name: String,
+    pub bytes: u64,
+}
+
+#[derive(Serialize, Clone, Debug, Default)]
+pub struct QuotaReport {
+    pub limit_bytes: u64, // 0 = no limit
+    pub before_bytes: u64,
+    pub after_bytes: u64,
+    pub removed: Vec<RemovedGame>,
+}
+
+/// Deletes the least recently downloaded games until the library fits in
+/// `max_library_size_bytes`. Favorites are never removed, and neither are games the app
+/// has no finished download of (imported or found on disk): those are the user's own copies.
+#[tauri::command]
+pub async fn enforce_library_quota(window: Window) -> Result<QuotaReport, AppError> {
+    let quota_task = task::spawn_blocking(move || -> Result<QuotaReport, AppError> {
+        let limit_bytes = read_u64_setting("max_library_size_bytes", 0)?;
+        let mut report = QuotaReport {
+            limit_bytes,
+            ..Default::default()
+        };
+
+        let conn = open_db()?;
+        // Oldest finished download first. Every downloaded game counts towards the size,
+        // but only ones with a finished download at a recorded path can be evicted.
+        let mut stmt = conn
+            .prepare(&format!(
+                "SELECT {}, d.last_done IS NOT NULL
+                 FROM {}
+                 LEFT JOIN (
+                     SELECT game_name, console, MAX(finished_at) AS last_done
+                     FROM downloads WHERE state = 'done' AND path IS NOT NULL GROUP BY game_name, console
+                 ) d ON d.game_name = games.name AND d.console = games.console
+                 WHERE is_downloaded = 1
+                 ORDER BY d.last_done IS NULL, d.last_done, games.id",
+                GAME_COLUMNS, GAME_TABLES
+            ))
+            .map_err(|e| AppError::db(format!("Failed to prepare query: {}", e)))?;
+        let games = stmt
+            .query_map([], |row| Ok((game_from_row(row)?, row.get::<_, bool>(12)?)))
+            .map_err(|e| AppError::db(format!("Query execution failed: {}", e)))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| AppError::db(format!("Row error: {}", e)))?;
+
+        let mut sized = Vec::new();
+        for (game, downloaded_here) in games {
+            let bytes = game_disk_size(&game)?;
+            report.before_bytes += bytes;
+            sized.push((game, downloaded_here, bytes));
+        }
+
+        let mut total = report.before_bytes;
+        if limit_bytes > 0 {
+            for (game, downloaded_here, bytes) in sized {
+                if total <= limit_bytes {
+                    break;
+                }
+                // A redownload in progress would put the files back behind it
+                if game.is_favorite || !downloaded_here || queue::is_tracked(game.id as u32) {
+                    continue;
+                }
+                remove_download(&window, &game)?;
+                total = total.saturating_sub(bytes);
+                report.removed.push(RemovedGame {
+                    id: game.id,
+                    name: game.name,
+                    bytes,
+                });
+            }
+        }
+
+        report.after_bytes = total;
+        Ok(report)
+    });
+
+    quota_task.await.map_err(|e| AppError::internal(e.to_string()))?
+}
+
+#[derive(Serialize, Clone, Debug, Default)]
+pub struct CleanupReport {
+    pub removed: Vec<String>, // paths deleted
+    pub reclaimed_bytes: u64,
+}
+
+/// `name` without the " (n)" queue::claim_path adds when two downloads want the same file.
+fn without_claim_suffix(name: &str) -> String {
+    let ext_len = file_extension(name).map(|e| e.len() + 1).unwrap_or(0);
+    let (stem, ext) = name.split_at(name.len() - ext_len);
+
+    let numbered = stem
+        .strip_suffix(')')
+        .and_then(|s| s.rsplit_once(" ("))
+        .filter(|(_, n)| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()));
+    match numbered {
+        Some((base, _)) => format!("{}{}", base, ext),
+        None => name.to_string(),
+    }
+}
+
+/// Archive names of games whose latest download attempt never finished (failed, cancelled,
+/// or cut off by a crash), i.e. ones this app started. History is matched on (name, console)
+/// since a full rebuild reuses game ids.
+fn unfinished_archive_names(conn: &Connection) -> Result<HashSet<String>, AppError> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT DISTINCT g.name, g.dl_link FROM downloads d
+             JOIN games g ON g.name = d.game_name AND g.console = d.console
+             WHERE d.id IN (SELECT MAX(id) FROM downloads GROUP BY game_name, console)
+               AND d.state IN ('queued', 'downloading', 'extracting', 'failed', 'cancelled')",
+        )
+        .map_err(|e| AppError::db(format!("Failed to prepare query: {}", e)))?;
+    let names = stmt
+        .query_map([], |row| Ok(archive_file_name(&row.get::<_, String>(0)?, &row.get::<_, String>(1)?)))
+        .map_err(|e| AppError::db(format!("Query execution failed: {}", e)))?
+        .collect::<Result<HashSet<_>, _>>()
+        .map_err(|e| AppError::db(format!("Row error: {}", e)))?;
+    Ok(names)
+}
+
+/// Everything a finished download recorded, plus the archive names of games that are
+/// downloaded or ever finished a download. The latter still count after a full rebuild
+/// resets `is_downloaded`, and after a failed redownload over a finished copy.
+fn kept_paths_and_names(conn: &Connection) -> Result<(HashSet<PathBuf>, HashSet<String>), AppError> {
+    let mut stmt = conn
+        .prepare("SELECT path FROM downloads WHERE state = 'done' AND path IS NOT NULL")
+        .map_err(|e| AppError::db(format!("Failed to prepare query: {}", e)))?;
+    let paths = stmt
+        .query_map([], |row| row.get::<_, String>(0).map(PathBuf::from))
+        .map_err(|e| AppError::db(format!("Query execution failed: {}", e)))?
+        .collect::<Result<HashSet<_>, _>>()
+        .map_err(|e| AppError::db(format!("Row error: {}", e)))?;
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT name, dl_link FROM games g
+             WHERE is_downloaded = 1
+                OR EXISTS (
+                    SELECT 1 FROM downloads d
+                    WHERE d.game_name = g.name AND d.console = g.console AND d.state = 'done'
+                )",
+        )
+        .map_err(|e| AppError::db(format!("Failed to prepare query: {}", e)))?;
+    let names = stmt
+        .query_map([], |row| Ok(archive_file_name(&row.get::<_, String>(0)?, &row.get::<_, String>(1)?)))
+        .map_err(|e| AppError::db(format!("Query execution failed: {}", e)))?
+        .collect::<Result<HashSet<_>, _>>()
+        .map_err(|e| AppError::db(format!("Row error: {}", e)))?;
+
+    Ok((paths, names))
+}
+
+/// Hidden `.<name>.partial` / `.<name>.extracting` leftovers of an interrupted copy or extraction.
+fn is_partial_leftover(name: &str) -> bool {
+    name.starts_with('.') && (name.ends_with(".partial") || name.ends_with(".extracting"))
+}
+
+/// Removes what crashed or failed downloads left behind in the download dirs and staging dir:
+/// hidden partial copies/extractions, and archives of games whose download never finished.
+/// Extracted folders, archives of downloaded games and anything the app didn't start are
+/// left alone. Refuses to run while downloads are queued or running.
+pub(crate) fn cleanup_partials() -> Result<CleanupReport, AppError> {
+    if !queue::get_active_downloads().is_empty() {
+        return Err(AppError::invalid_input("Downloads are in progress; clean up once they finish"));
+    }
+
+    let conn = open_db()?;
+    let unfinished = unfinished_archive_names(&conn)?;
+    let (kept_paths, kept_names) = kept_paths_and_names(&conn)?;
+
+    let mut roots = download_roots(&conn)?;
+    if let Some(staging) = staging_dir()? {
+        if !roots.contains(&staging) {
+            roots.push(staging);
+        }
+    }
+
+    let mut stmt = conn
+        .prepare("SELECT console FROM consoles")
+        .map_err(|e| AppError::db(format!("Failed to prepare query: {}", e)))?;
+    let console_dirs = stmt
+        .query_map([], |row| row.get::<_, String>(0).map(|c| sanitize_file_name(&c)))
+        .map_err(|e| AppError::db(format!("Query execution failed: {}", e)))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| AppError::db(format!("Row error: {}", e)))?;
+
+    let mut doomed = Vec::new();
+    for root in roots {
+        // organize_by_console puts extracted folders (and their partial siblings) one level
+        // down; game folders themselves are never looked into
+        let mut dirs = vec![root.clone()];
+        dirs.extend(console_dirs.iter().map(|c| root.join(c)).filter(|d| d.is_dir()));
+
+        for dir in dirs {
+            let Ok(entries) = fs::read_dir(&dir) else {
+                continue;
+            };
+            for path in entries.flatten().map(|e| e.path()) {
+                let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+                    continue;
+                };
+                if kept_paths.contains(&path) {
+                    continue;
+                }
+                let leftover = is_partial_leftover(name)
+                    || (dir == root
+                        && path.is_file()
+                        && !kept_names.contains(name)
+                        && unfinished.contains(&without_claim_suffix(name)));
+                if leftover && !doomed.contains(&path) {
+                    doomed.push(path);
+                }
+            }
+        }
+    }
+
+    let mut report = CleanupReport::default();
+    for path in doomed {
+        let bytes = path_size(&path);
+        let removed = if path.is_dir() {
+            fs::remove_dir_all(&path)
+        } else {
+            fs::remove_file(&path)
+        };
+        match removed {
+            Ok(()) => {
+                report.reclaimed_bytes += bytes;
+                report.removed.push(path.to_string_lossy().to_string());
+            }
+            Err(e) => warn!("Failed to remove leftover {:?}: {}", path, e),
+        }
+    }
+
+    info!("Removed {} partial downloads ({} bytes)", report.removed.len(), report.reclaimed_bytes);
+    Ok(report)
+}
+
+/// Removes leftovers of failed or interrupted downloads; see `cleanup_partials`.
+#[tauri::command]
+pub async fn cleanup_partial_downloads() -> Result<CleanupReport, AppError> {
+    task::spawn_blocking(cleanup_partials)
+        .await
+        .map_err(|e| AppError::internal(e.to_string()))?
+}
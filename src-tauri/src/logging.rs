@@ -0,0 +1,177 @@
+use std::fs::{self, File};
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use tracing::level_filters::LevelFilter;
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{fmt, reload, Registry};
+
+use crate::error::AppError;
+use crate::paths::data_dir;
+use crate::settings::read_setting;
+
+pub const DEFAULT_LOG_LEVEL: &str = "info";
+const LOG_FILE_PREFIX: &str = "rusty-roms.log";
+// Daily files kept; older ones are deleted as the log rotates
+const MAX_LOG_FILES: usize = 14;
+// Bytes read at a time when reading a log file back from its end
+const TAIL_BLOCK: u64 = 64 * 1024;
+
+// Keeps the file writer flushing for the life of the app
+static GUARD: OnceLock<WorkerGuard> = OnceLock::new();
+// Lets set_log_level change the filter without a restart
+static LEVEL: OnceLock<reload::Handle<LevelFilter, Registry>> = OnceLock::new();
+
+pub fn logs_dir() -> PathBuf {
+    data_dir().join("logs")
+}
+
+pub fn parse_level(level: &str) -> Result<LevelFilter, AppError> {
+    level
+        .trim()
+        .parse::<LevelFilter>()
+        .map_err(|_| {
+            AppError::invalid_input(format!(
+                "Unknown log level {:?} (expected error, warn, info, debug or trace)",
+                level
+            ))
+        })
+}
+
+/// Logs to stdout and a daily-rotated file in `<data dir>/logs`, at the `log_level` setting.
+/// Only the last `MAX_LOG_FILES` days are kept.
+pub fn init() {
+    let level = read_setting("log_level")
+        .ok()
+        .flatten()
+        .and_then(|l| parse_level(&l).ok())
+        .unwrap_or(LevelFilter::INFO);
+
+    let appender = match RollingFileAppender::builder()
+        .rotation(Rotation::DAILY)
+        .filename_prefix(LOG_FILE_PREFIX)
+        .max_log_files(MAX_LOG_FILES)
+        .build(logs_dir())
+    {
+        Ok(appender) => appender,
+        Err(e) => {
+            eprintln!("Logging setup failed: {}", e);
+            return;
+        }
+    };
+    let (file_writer, guard) = tracing_appender::non_blocking(appender);
+    let (filter, handle) = reload::Layer::new(level);
+
+    let result = tracing_subscriber::registry()
+        .with(filter)
+        .with(fmt::layer())
+        .with(fmt::layer().with_ansi(false).with_writer(file_writer))
+        .try_init();
+
+    if let Err(e) = result {
+        eprintln!("Logging setup failed: {}", e);
+        return;
+    }
+    let _ = GUARD.set(guard);
+    let _ = LEVEL.set(handle);
+}
+
+pub fn set_level(level: LevelFilter) -> Result<(), AppError> {
+    match LEVEL.get() {
+        Some(handle) => handle
+            .reload(level)
+            .map_err(|e| AppError::internal(format!("Failed to change log level: {}", e))),
+        None => Ok(()), // not initialised; the setting applies on next start
+    }
+}
+
+/// The last `wanted` lines of `path`, oldest first. Reads back from the end a block at a
+/// time, so a long log costs no more than the lines asked for.
+fn tail_lines(path: &Path, wanted: usize) -> std::io::Result<Vec<String>> {
+    let mut file = File::open(path)?;
+    let len = file.metadata()?.len();
+
+    // Read until the buffer holds more than `wanted` line breaks (the extra one marks where
+    // the first wanted line starts) or the whole file is in
+    let mut start = len;
+    let mut buf: Vec<u8> = Vec::new();
+    while start > 0 && buf.iter().filter(|&&b| b == b'\n').count() <= wanted {
+        let block = TAIL_BLOCK.min(start);
+        start -= block;
+        let mut chunk = vec![0u8; block as usize];
+        file.seek(SeekFrom::Start(start))?;
+        file.read_exact(&mut chunk)?;
+        chunk.append(&mut buf);
+        buf = chunk;
+    }
+
+    let text = String::from_utf8_lossy(&buf);
+    let mut lines: Vec<&str> = text.lines().collect();
+    // Unless the read reached the file's start, the first line may be cut off
+    if start > 0 && !lines.is_empty() {
+        lines.remove(0);
+    }
+    let skip = lines.len().saturating_sub(wanted);
+    Ok(lines[skip..].iter().map(|l| l.to_string()).collect())
+}
+
+/// The last `lines` log lines, oldest first, reading back through rotated files as needed.
+#[tauri::command]
+pub fn get_recent_logs(lines: u32) -> Result<Vec<String>, AppError> {
+    let wanted = lines as usize;
+    let dir = logs_dir();
+
+    let mut files: Vec<PathBuf> = match fs::read_dir(&dir) {
+        Ok(entries) => entries
+            .flatten()
+            .map(|e| e.path())
+            .filter(|p| {
+                p.file_name()
+                    .and_then(|n| n.to_str())
+                    .is_some_and(|n| n.starts_with(LOG_FILE_PREFIX))
+            })
+            .collect(),
+        Err(_) => return Ok(Vec::new()),
+    };
+    // Daily files are suffixed with the date, so name order is age order
+    files.sort();
+
+    let mut collected: Vec<String> = Vec::new();
+    for file in files.iter().rev() {
+        if collected.len() >= wanted {
+            break;
+        }
+        let keep = wanted - collected.len();
+        let mut chunk = tail_lines(file, keep)
+            .map_err(|e| AppError::io(format!("Failed to read {:?}: {}", file, e)))?;
+        chunk.append(&mut collected);
+        collected = chunk;
+    }
+
+    Ok(collected)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tail_lines_reads_across_blocks() {
+        let path = std::env::temp_dir().join(format!("rusty-roms-tail-{}.log", std::process::id()));
+        // Long enough that the wanted lines span more than one block
+        let lines: Vec<String> = (0..20_000).map(|i| format!("line {}", i)).collect();
+        fs::write(&path, lines.join("\n") + "\n").unwrap();
+
+        let tail = tail_lines(&path, 15_000).unwrap();
+        assert_eq!(tail.len(), 15_000);
+        assert_eq!(tail.first().map(String::as_str), Some("line 5000"));
+        assert_eq!(tail.last().map(String::as_str), Some("line 19999"));
+        assert_eq!(tail_lines(&path, 30_000).unwrap().len(), 20_000);
+
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,347 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use rusqlite::{Connection, OpenFlags, MAIN_DB};
+use serde::Serialize;
+
+use crate::data;
+use crate::error::AppError;
+use crate::history::now_unix;
+use crate::db;
+use crate::paths::db_path;
+use crate::queue;
+
+fn open_db() -> Result<Connection, AppError> {
+    db::open().map_err(|e| AppError::db(format!("Failed to open DB: {}", e)))
+}
+
+fn db_file_size() -> u64 {
+    fs::metadata(db_path()).map(|m| m.len()).unwrap_or(0)
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct CompactResult {
+    pub before_bytes: u64,
+    pub after_bytes: u64,
+}
+
+/// Rewrites the DB file without the free pages left behind by re-scrapes.
+pub fn compact() -> Result<CompactResult, AppError> {
+    let before_bytes = db_file_size();
+
+    let conn = open_db()?;
+    // Checkpoint so the shrunk pages land in the main file, not just the WAL
+    conn.execute_batch("VACUUM; PRAGMA optimize; PRAGMA wal_checkpoint(TRUNCATE);")
+        .map_err(|e| AppError::db(format!("Failed to compact DB: {}", e)))?;
+    drop(conn);
+
+    Ok(CompactResult {
+        before_bytes,
+        after_bytes: db_file_size(),
+    })
+}
+
+#[tauri::command]
+pub async fn compact_database() -> Result<CompactResult, AppError> {
+    tokio::task::spawn_blocking(compact)
+        .await
+        .map_err(|e| AppError::internal(e.to_string()))?
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct CleanupCounts {
+    pub duplicate_consoles: usize,
+    pub duplicate_games: usize,
+    pub bad_rows: usize,
+}
+
+/// Re-runs the post-scrape cleanup passes without scraping, to tidy up after manual
+/// edits or a scrape that stopped halfway. Returns the rows each pass removed.
+#[tauri::command]
+pub async fn clean_database() -> Result<CleanupCounts, AppError> {
+    let clean_task = tokio::task::spawn_blocking(move || -> Result<CleanupCounts, AppError> {
+        // Same order as after a scrape
+        let duplicate_consoles = data::duplicate_cleanup_consoles()
+            .map_err(|e| AppError::db(format!("Failed to remove duplicate consoles: {}", e)))?;
+        let duplicate_games = data::duplicate_cleanup_games()
+            .map_err(|e| AppError::db(format!("Failed to remove duplicate games: {}", e)))?;
+        let bad_rows = data::remove_bad_data()
+            .map_err(|e| AppError::db(format!("Failed to remove bad rows: {}", e)))?;
+
+        Ok(CleanupCounts {
+            duplicate_consoles,
+            duplicate_games,
+            bad_rows,
+        })
+    });
+
+    clean_task.await.map_err(|e| AppError::internal(e.to_string()))?
+}
+
+// Tables and columns the current schema needs; checked by check_database
+const EXPECTED_SCHEMA: &[(&str, &[&str])] = &[
+    ("consoles", &["id", "console", "url", "headers", "last_scraped"]),
+    (
+        "games",
+        &[
+            "id", "name", "console", "date", "size", "dl_link", "is_downloaded", "size_bytes",
+            "extension", "available", "clean_name", "date_unix", "first_seen",
+        ],
+    ),
+    ("downloads", &["id", "game_id", "state", "started_at", "finished_at", "error", "bytes_downloaded", "path", "etag", "last_modified", "game_name", "console"]),
+    ("games_fts", &["search_key", "clean_key"]),
+    ("console_dirs", &["console", "path"]),
+    ("favorites", &["game_name", "game_console", "added_at"]),
+    ("scrape_filters", &["id", "kind", "pattern"]),
+    ("game_parts", &["game_id", "part", "url"]),
+    ("seen_before", &["name", "console", "first_seen"]),
+    ("meta", &["key", "value"]),
+];
+
+// Problems integrity_check lists before stopping
+const MAX_INTEGRITY_ERRORS: u32 = 100;
+
+#[derive(Serialize, Clone, Debug)]
+pub struct DbHealth {
+    pub ok: bool,
+    pub integrity: Vec<String>, // just "ok" when the file is intact
+    pub missing: Vec<String>,   // "table" or "table.column"
+    pub schema_version: i64,
+    pub latest_schema_version: i64,
+    // "restore" (from a backup, or a full rebuild) when the file is corrupt,
+    // "rebuild" when only the schema is off
+    pub remedy: Option<String>,
+}
+
+fn table_exists(conn: &Connection, table: &str) -> Result<bool, AppError> {
+    conn.query_row(
+        "SELECT COUNT(*) FROM sqlite_master WHERE name = ?1 AND type = 'table'",
+        [table],
+        |row| row.get::<_, i64>(0),
+    )
+    .map(|n| n > 0)
+    .map_err(|e| AppError::db(format!("Failed to read schema: {}", e)))
+}
+
+/// Runs SQLite's integrity check and verifies the expected tables and columns are
+/// there, so users get a diagnosis (and a way out) instead of cryptic errors later.
+#[tauri::command]
+pub async fn check_database() -> Result<DbHealth, AppError> {
+    let check_task = tokio::task::spawn_blocking(move || -> Result<DbHealth, AppError> {
+        let conn = open_db()?;
+
+        // A badly damaged file can fail the check itself; that's a corruption report too
+        let integrity = conn
+            .prepare(&format!("PRAGMA integrity_check({})", MAX_INTEGRITY_ERRORS))
+            .and_then(|mut stmt| {
+                stmt.query_map([], |row| row.get::<_, String>(0))?
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .unwrap_or_else(|e| vec![e.to_string()]);
+
+        if integrity.len() != 1 || integrity[0] != "ok" {
+            // The schema can't be trusted either, so don't bother reading it
+            return Ok(DbHealth {
+                ok: false,
+                integrity,
+                missing: Vec::new(),
+                schema_version: 0,
+                latest_schema_version: data::latest_schema_version(),
+                remedy: Some("restore".to_string()),
+            });
+        }
+
+        let mut missing = Vec::new();
+        for (table, columns) in EXPECTED_SCHEMA {
+            if !table_exists(&conn, table)? {
+                missing.push(table.to_string());
+                continue;
+            }
+            for column in *columns {
+                let found = data::has_column(&conn, table, column)
+                    .map_err(|e| AppError::db(format!("Failed to read schema: {}", e)))?;
+                if !found {
+                    missing.push(format!("{}.{}", table, column));
+                }
+            }
+        }
+
+        let schema_version = data::schema_version(&conn)
+            .map_err(|e| AppError::db(format!("Failed to read schema version: {}", e)))?;
+
+        let remedy = (!missing.is_empty() || schema_version < data::latest_schema_version())
+            .then(|| "rebuild".to_string());
+
+        Ok(DbHealth {
+            ok: remedy.is_none(),
+            integrity,
+            missing,
+            schema_version,
+            latest_schema_version: data::latest_schema_version(),
+            remedy,
+        })
+    });
+
+    check_task.await.map_err(|e| AppError::internal(e.to_string()))?
+}
+
+/// Copies the live DB with SQLite's online backup API. `dest` may be a folder
+/// (a timestamped file is created inside) or a file path. Returns the written path.
+#[tauri::command]
+pub fn backup_database(dest: String) -> Result<String, AppError> {
+    if dest.trim().is_empty() {
+        return Err(AppError::invalid_input("Backup path cannot be empty"));
+    }
+
+    let mut target = PathBuf::from(&dest);
+    if target.is_dir() {
+        target.push(format!("games-backup-{}.db", now_unix()));
+    }
+    if target == db_path() {
+        return Err(AppError::invalid_input("Backup path is the live database"));
+    }
+
+    let conn = open_db()?;
+    conn.backup(MAIN_DB, &target, None)
+        .map_err(|e| AppError::db(format!("Backup failed: {}", e)))?;
+
+    Ok(target.to_string_lossy().to_string())
+}
+
+/// Checks `src` is an intact rusty-roms DB this version can migrate.
+fn validate_backup(src: &Path) -> Result<(), AppError> {
+    let conn = Connection::open_with_flags(src, OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .map_err(|e| AppError::io(format!("Failed to open {:?}: {}", src, e)))?;
+
+    let integrity: String = conn
+        .query_row("PRAGMA integrity_check", [], |row| row.get(0))
+        .map_err(|e| AppError::invalid_input(format!("{:?} is not a SQLite database: {}", src, e)))?;
+    if integrity != "ok" {
+        return Err(AppError::invalid_input(format!("{:?} is corrupt: {}", src, integrity)));
+    }
+
+    for table in ["games", "consoles"] {
+        let found: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = ?1",
+                [table],
+                |row| row.get(0),
+            )
+            .map_err(|e| AppError::db(format!("Failed to read schema: {}", e)))?;
+        if found == 0 {
+            return Err(AppError::invalid_input(format!("{:?} is not a rusty-roms database (no {} table)", src, table)));
+        }
+    }
+
+    // Read-only, so look at meta directly instead of going through schema_version()
+    let version: i64 = conn
+        .query_row("SELECT value FROM meta WHERE key = 'schema_version'", [], |row| {
+            row.get::<_, String>(0)
+        })
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(1);
+    if version > data::latest_schema_version() {
+        return Err(AppError::invalid_input(format!(
+            "Backup is from a newer version (schema {}, this app supports {})",
+            version,
+            data::latest_schema_version()
+        )));
+    }
+
+    Ok(())
+}
+
+struct DbUsers {
+    scrapes: usize,  // scrapes between begin_scrape and the guard's drop
+    restoring: bool, // restore_database is swapping the file
+}
+
+// Scrapes and downloads write as they go, so a restore waits for none to be running and
+// keeps new ones from starting until it's done
+static DB_USERS: Mutex<DbUsers> = Mutex::new(DbUsers { scrapes: 0, restoring: false });
+
+fn db_users() -> std::sync::MutexGuard<'static, DbUsers> {
+    DB_USERS.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn restoring_error() -> AppError {
+    AppError::invalid_input("A backup is being restored; try again once it's done")
+}
+
+/// Counts a running scrape until dropped.
+pub struct ScrapeGuard(());
+
+impl Drop for ScrapeGuard {
+    fn drop(&mut self) {
+        let mut users = db_users();
+        users.scrapes = users.scrapes.saturating_sub(1);
+    }
+}
+
+/// Registers a scrape for its whole run; refused while a backup is being restored.
+pub fn begin_scrape() -> Result<ScrapeGuard, AppError> {
+    let mut users = db_users();
+    if users.restoring {
+        return Err(restoring_error());
+    }
+    users.scrapes += 1;
+    Ok(ScrapeGuard(()))
+}
+
+/// `queue::track`, refused while a backup is being restored.
+pub fn track_download(id: u32) -> Result<bool, AppError> {
+    // Tracked under the lock, so restore_database either sees it or refuses it
+    let users = db_users();
+    if users.restoring {
+        return Err(restoring_error());
+    }
+    Ok(queue::track(id))
+}
+
+// Held by restore_database from its checks to the end
+struct RestoreGuard(());
+
+impl Drop for RestoreGuard {
+    fn drop(&mut self) {
+        db_users().restoring = false;
+    }
+}
+
+fn begin_restore() -> Result<RestoreGuard, AppError> {
+    let mut users = db_users();
+    if users.restoring {
+        return Err(restoring_error());
+    }
+    if users.scrapes > 0 {
+        return Err(AppError::invalid_input("Can't restore while the library is updating"));
+    }
+    // Queued ones count too: they'd start writing history partway through
+    if queue::tracked_count() > 0 {
+        return Err(AppError::invalid_input("Can't restore while downloads are queued or running"));
+    }
+    users.restoring = true;
+    Ok(RestoreGuard(()))
+}
+
+/// Replaces the live DB with a validated backup, then migrates it to the current schema.
+/// Scrapes and downloads can't start until it's done.
+#[tauri::command]
+pub fn restore_database(src: String) -> Result<(), AppError> {
+    let src = PathBuf::from(src);
+    if !src.is_file() {
+        return Err(AppError::not_found(format!("Backup not found: {:?}", src)));
+    }
+
+    let _restoring = begin_restore()?;
+
+    validate_backup(&src)?;
+
+    let mut conn = open_db()?;
+    conn.restore(MAIN_DB, &src, None::<fn(rusqlite::backup::Progress)>)
+        .map_err(|e| AppError::db(format!("Restore failed: {}", e)))?;
+
+    data::run_migrations(&conn).map_err(|e| AppError::db(format!("Failed to migrate restored DB: {}", e)))?;
+    Ok(())
+}
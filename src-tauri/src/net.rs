@@ -0,0 +1,204 @@
+use reqwest::blocking::{Client, ClientBuilder};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use std::collections::BTreeMap;
+use std::sync::{Condvar, Mutex};
+use std::time::Duration;
+
+use crate::db;
+use crate::error::AppError;
+use crate::settings::{read_setting, read_u64_setting};
+
+pub const DEFAULT_USER_AGENT: &str = concat!("rusty-roms/", env!("CARGO_PKG_VERSION"));
+pub const DEFAULT_REQUEST_TIMEOUT_SECS: u64 = 30;
+
+// Redirect hops followed before giving up, e.g. after a mirror moves a folder.
+pub const MAX_REDIRECTS: usize = 5;
+
+/// Client builder with the user's network settings (proxy, timeout, User-Agent) applied.
+/// Callers can layer their own options (e.g. a shorter timeout) on top.
+pub fn client_builder() -> Result<ClientBuilder, AppError> {
+    // For blocking clients the timeout covers connecting and each read, not the whole body
+    let timeout_secs = read_u64_setting("request_timeout_secs", DEFAULT_REQUEST_TIMEOUT_SECS)?;
+
+    let user_agent = read_setting("user_agent")?
+        .filter(|ua| !ua.trim().is_empty())
+        .unwrap_or_else(|| DEFAULT_USER_AGENT.to_string());
+
+    let mut builder = Client::builder()
+        .timeout(Duration::from_secs(timeout_secs))
+        .user_agent(user_agent)
+        .redirect(reqwest::redirect::Policy::limited(MAX_REDIRECTS))
+        // Archives must arrive byte-for-byte so ranges and Content-Length line up;
+        // only scrape_client opts into compression
+        .no_gzip()
+        .no_brotli()
+        .no_deflate();
+
+    if let Some(proxy_url) = read_setting("proxy_url")?.filter(|p| !p.trim().is_empty()) {
+        let proxy = reqwest::Proxy::all(proxy_url.trim())
+            .map_err(|e| AppError::invalid_input(format!("Invalid proxy_url setting: {}", e)))?;
+        builder = builder.proxy(proxy);
+    }
+
+    Ok(builder)
+}
+
+/// The client every download, HEAD probe and scrape request should go through.
+pub fn build_client() -> Result<Client, AppError> {
+    client_builder()?
+        .build()
+        .map_err(|e| AppError::network(format!("Client build failed: {}", e)))
+}
+
+/// Client for HTML listings: like `build_client`, but asks for gzip/brotli/deflate
+/// responses, which `text()` decompresses transparently.
+pub fn scrape_client() -> Result<Client, AppError> {
+    client_builder()?
+        .gzip(true)
+        .brotli(true)
+        .deflate(true)
+        .build()
+        .map_err(|e| AppError::network(format!("Client build failed: {}", e)))
+}
+
+/// Parses a console's `headers` column (a JSON object of name -> value).
+/// NULL or empty means no extra headers.
+pub fn parse_headers(json: Option<&str>) -> Result<HeaderMap, AppError> {
+    let mut headers = HeaderMap::new();
+    let Some(json) = json.filter(|j| !j.trim().is_empty()) else {
+        return Ok(headers);
+    };
+
+    let pairs: BTreeMap<String, String> = serde_json::from_str(json)
+        .map_err(|e| AppError::invalid_input(format!("Headers must be a JSON object of strings: {}", e)))?;
+    for (name, value) in pairs {
+        let header = HeaderName::from_bytes(name.trim().as_bytes())
+            .map_err(|e| AppError::invalid_input(format!("Invalid header name {:?}: {}", name, e)))?;
+        let value = HeaderValue::from_str(value.trim())
+            .map_err(|e| AppError::invalid_input(format!("Invalid value for header {}: {}", name, e)))?;
+        headers.insert(header, value);
+    }
+    Ok(headers)
+}
+
+/// Extra headers configured for `console`; empty if it has none or isn't a known source.
+pub fn console_headers(console: &str) -> Result<HeaderMap, AppError> {
+    let conn = db::open().map_err(|e| AppError::db(format!("Failed to open DB: {}", e)))?;
+    let json: Option<String> = match conn.query_row(
+        "SELECT headers FROM consoles WHERE console = ?1",
+        [console],
+        |row| row.get(0),
+    ) {
+        Ok(json) => json,
+        Err(rusqlite::Error::QueryReturnedNoRows) => None,
+        Err(e) => return Err(AppError::db(format!("Failed to read headers for {}: {}", console, e))),
+    };
+    parse_headers(json.as_deref())
+}
+
+/// `build_client` plus the console's extra headers, for fetching files from that source.
+pub fn source_client(console: Option<&str>) -> Result<Client, AppError> {
+    let headers = match console {
+        Some(console) => console_headers(console)?,
+        None => HeaderMap::new(),
+    };
+    client_builder()?
+        .default_headers(headers)
+        .build()
+        .map_err(|e| AppError::network(format!("Client build failed: {}", e)))
+}
+
+/// Default `max_connections`. Three downloads on up to four chunks each can ask for
+/// more than this, so chunks past the limit wait for a free permit. Raising it mostly
+/// helps big files on fast links.
+pub const DEFAULT_MAX_CONNECTIONS: u64 = 8;
+
+// Requests open right now, app-wide, across downloads, HEAD probes and scraping
+static OPEN_CONNECTIONS: Mutex<usize> = Mutex::new(0);
+static CONNECTION_FREED: Condvar = Condvar::new();
+
+fn max_connections() -> usize {
+    read_u64_setting("max_connections", DEFAULT_MAX_CONNECTIONS)
+        .unwrap_or(DEFAULT_MAX_CONNECTIONS)
+        .max(1) as usize
+}
+
+/// One open request counted against `max_connections`; dropping it lets the next one go.
+pub struct ConnectionPermit(());
+
+impl Drop for ConnectionPermit {
+    fn drop(&mut self) {
+        let mut open = OPEN_CONNECTIONS.lock().unwrap_or_else(|e| e.into_inner());
+        *open = open.saturating_sub(1);
+        CONNECTION_FREED.notify_one();
+    }
+}
+
+/// Blocks until fewer than `max_connections` requests are open, then counts this one.
+/// Hold the permit until the response has been read, and never take a second one while
+/// holding it.
+pub fn connection_permit() -> ConnectionPermit {
+    connection_permit_unless(|| false).expect("a wait that's never cancelled ends with a permit")
+}
+
+/// `connection_permit`, giving up with `None` once `cancelled` says so, e.g. for a
+/// download stopped while it waited.
+pub fn connection_permit_unless(cancelled: impl Fn() -> bool) -> Option<ConnectionPermit> {
+    loop {
+        if cancelled() {
+            return None;
+        }
+        // Read on every pass, outside the lock, so a raised limit lets waiters through
+        let limit = max_connections();
+        let mut open = OPEN_CONNECTIONS.lock().unwrap_or_else(|e| e.into_inner());
+        if *open < limit {
+            *open += 1;
+            return Some(ConnectionPermit(()));
+        }
+        // Wake up now and then in case the limit was raised or the wait was cancelled
+        let _ = CONNECTION_FREED
+            .wait_timeout(open, Duration::from_secs(1))
+            .unwrap_or_else(|e| e.into_inner());
+    }
+}
+
+/// `url` with its scheme, host and port taken from `base`; unchanged if either won't parse.
+pub fn rewrite_host(url: &str, base: &str) -> String {
+    let (Ok(mut parsed), Ok(base)) = (reqwest::Url::parse(url), reqwest::Url::parse(base)) else {
+        return url.to_string();
+    };
+    let Some(host) = base.host_str() else {
+        return url.to_string();
+    };
+
+    if parsed.set_scheme(base.scheme()).is_err()
+        || parsed.set_host(Some(host)).is_err()
+        || parsed.set_port(base.port()).is_err()
+    {
+        return url.to_string();
+    }
+    parsed.to_string()
+}
+
+/// The `mirror_base` setting, if one is set.
+pub fn mirror_base() -> Result<Option<String>, AppError> {
+    Ok(read_setting("mirror_base")?.filter(|b| !b.trim().is_empty()))
+}
+
+// Host of the built-in console sources; the only one a mirror stands in for
+const MYRIENT_HOST: &str = "myrient.erista.me";
+
+/// Points a stored Myrient console or game URL at the preferred mirror, so switching
+/// mirrors doesn't need a re-scrape. URLs of user-added sources on other hosts are left alone.
+pub fn apply_mirror(url: &str) -> Result<String, AppError> {
+    let on_myrient = reqwest::Url::parse(url)
+        .ok()
+        .is_some_and(|u| u.host_str() == Some(MYRIENT_HOST));
+    if !on_myrient {
+        return Ok(url.to_string());
+    }
+    Ok(match mirror_base()? {
+        Some(base) => rewrite_host(url, &base),
+        None => url.to_string(),
+    })
+}
@@ -0,0 +1,61 @@
+use std::path::PathBuf;
+use crate::error::AppError;
+
+// Overrides where the DB (and default downloads) live, e.g. another drive or a test temp dir
+pub const DATA_DIR_ENV: &str = "RUSTY_ROMS_DATA_DIR";
+
+/// `$RUSTY_ROMS_DATA_DIR` if set, else `~/.rusty-roms`. Created on first use.
+pub fn data_dir() -> PathBuf {
+    let p = match std::env::var_os(DATA_DIR_ENV).filter(|v| !v.is_empty()) {
+        Some(dir) => PathBuf::from(dir),
+        None => {
+            let mut p = dirs::home_dir()
+                .expect("Could not determine home directory");
+            p.push(".rusty-roms");
+            p
+        }
+    };
+
+    std::fs::create_dir_all(&p)
+        .expect("Failed to create data directory");
+
+    p
+}
+
+pub fn db_path() -> PathBuf {
+    data_dir().join("games.db")
+}
+
+/// Where downloads go when no folder has been configured.
+pub fn default_download_dir() -> Result<PathBuf, AppError> {
+    Ok(data_dir().join("downloads"))
+}
+
+/// Moves anything left in the old `~/.roms-tauri` dir into the data dir, without
+/// overwriting files that already exist there, then removes the old dir if empty.
+pub fn migrate_legacy_data_dir() {
+    let Some(mut legacy) = dirs::home_dir() else {
+        return;
+    };
+    legacy.push(".roms-tauri");
+
+    let Ok(entries) = std::fs::read_dir(&legacy) else {
+        return;
+    };
+
+    let target_dir = data_dir();
+    for entry in entries.flatten() {
+        let target = target_dir.join(entry.file_name());
+        if target.exists() {
+            tracing::warn!("Not migrating {:?}: {:?} already exists", entry.path(), target);
+            continue;
+        }
+        match std::fs::rename(entry.path(), &target) {
+            Ok(()) => tracing::info!("Moved {:?} to {:?}", entry.path(), target),
+            Err(e) => tracing::warn!("Failed to move {:?} to {:?}: {}", entry.path(), target, e),
+        }
+    }
+
+    // Only succeeds once everything was moved
+    let _ = std::fs::remove_dir(&legacy);
+}
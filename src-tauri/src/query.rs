@@ -1,73 +1,902 @@
-use rusqlite::Connection;
-use tauri::command;
-use std::path::PathBuf;
-use dirs;
+use std::collections::BTreeMap;
+
+use rusqlite::{params_from_iter, types::Value, Connection};
+use strsim::normalized_damerau_levenshtein;
+use unicode_normalization::{char::is_combining_mark, UnicodeNormalization};
+use tauri::{command, Emitter, Window};
+
+use crate::db;
+use crate::error::AppError;
 
 #[derive(Clone, serde::Serialize)]
 pub struct Game {
     pub id: i64,
     pub name: String,
     pub console: String,
+    pub date: String,
     pub size: String,
+    pub size_bytes: Option<i64>,
+    pub extension: Option<String>,
     pub dl_link: String,
     pub is_downloaded: bool,
+    pub available: bool,
+    pub region: Option<String>,
+    pub is_favorite: bool,
+    pub date_unix: Option<i64>, // `date` parsed, when it could be
+}
+
+// Column list matching `game_from_row`; keep the two in sync.
+// Select it `FROM {GAME_TABLES}` so the favorites flag resolves.
+pub const GAME_COLUMNS: &str =
+    "id, name, console, date, size, size_bytes, extension, dl_link, is_downloaded, available, \
+     favorites.added_at IS NOT NULL, date_unix";
+
+pub const GAME_TABLES: &str =
+    "games LEFT JOIN favorites ON favorites.game_name = games.name AND favorites.game_console = games.console";
+
+pub fn game_from_row(row: &rusqlite::Row) -> rusqlite::Result<Game> {
+    let name: String = row.get(1)?;
+    Ok(Game {
+        id: row.get(0)?,
+        console: row.get(2)?,
+        date: row.get(3)?,
+        size: row.get(4)?,
+        size_bytes: row.get(5)?,
+        extension: row.get(6)?,
+        dl_link: row.get(7)?,
+        is_downloaded: row.get::<_, i64>(8)? != 0,
+        available: row.get::<_, i64>(9)? != 0,
+        is_favorite: row.get::<_, i64>(10)? != 0,
+        date_unix: row.get(11)?,
+        region: detect_region(&name),
+        name,
+    })
+}
+
+// Region names as they appear in No-Intro tags, e.g. "(USA, Europe)".
+const KNOWN_REGIONS: &[&str] = &[
+    "World", "USA", "Europe", "Japan", "Asia", "Australia", "Brazil", "Canada", "China",
+    "France", "Germany", "Hong Kong", "Italy", "Korea", "Netherlands", "Russia", "Spain",
+    "Sweden", "Taiwan", "UK",
+];
+
+/// Returns the first parenthesised tag made up of known regions, e.g. "USA, Europe".
+pub fn detect_region(name: &str) -> Option<String> {
+    name.split('(')
+        .skip(1)
+        .filter_map(|part| part.split_once(')').map(|(tag, _)| tag))
+        .find(|tag| {
+            tag.split(", ")
+                .all(|region| KNOWN_REGIONS.contains(&region))
+        })
+        .map(|tag| tag.to_string())
+}
+
+/// Every region that appears in the catalog with how many games carry it, sorted by
+/// name. A multi-region game like "(USA, Europe)" counts toward each region.
+#[command]
+pub fn list_regions() -> Result<Vec<(String, u64)>, AppError> {
+    let conn = db::open()
+        .map_err(|e| AppError::db(format!("Failed to open DB: {}", e)))?;
+    regions_in(&conn)
 }
 
-pub fn db_path() -> PathBuf {
-    let mut p = dirs::home_dir()
-        .expect("Could not determine home directory");
+fn regions_in(conn: &Connection) -> Result<Vec<(String, u64)>, AppError> {
+    let mut stmt = conn
+        .prepare("SELECT name FROM games")
+        .map_err(|e| AppError::db(format!("Failed to prepare query: {}", e)))?;
+    let names = stmt
+        .query_map([], |row| row.get::<_, String>(0))
+        .map_err(|e| AppError::db(format!("Query execution failed: {}", e)))?;
+
+    let mut counts: BTreeMap<String, u64> = BTreeMap::new();
+    for name in names {
+        let name = name.map_err(|e| AppError::db(format!("Row error: {}", e)))?;
+        if let Some(tag) = detect_region(&name) {
+            for region in tag.split(", ") {
+                *counts.entry(region.to_string()).or_default() += 1;
+            }
+        }
+    }
 
-    p.push(".rusty-roms");
+    Ok(counts.into_iter().collect())
+}
 
-    std::fs::create_dir_all(&p)
-        .expect("Failed to create .rusty-roms directory");
+#[derive(serde::Serialize)]
+pub struct SearchPage {
+    pub games: Vec<Game>,
+    pub total: u64,
+}
 
-    p.push("games.db");
-    p
+/// Optional narrowing applied on top of the name search.
+#[derive(Default, serde::Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SearchFilters {
+    pub console: Option<String>,
+    pub min_size: Option<i64>,
+    pub max_size: Option<i64>,
+    pub regions: Vec<String>,
+    pub exclude_betas: bool,
+    pub fuzzy: bool,
+    pub added_after: Option<i64>, // unix seconds, inclusive
+    pub added_before: Option<i64>,
 }
 
 #[command]
-pub fn search_games(search: String) -> Result<Vec<Game>, String> {
-    let conn = Connection::open(db_path())
-        .map_err(|e| format!("Failed to open DB: {}", e))?;
+pub fn search_games(
+    search: String,
+    filters: Option<SearchFilters>,
+    limit: u32,
+    offset: u32,
+) -> Result<SearchPage, AppError> {
+    let conn = db::open()
+        .map_err(|e| AppError::db(format!("Failed to open DB: {}", e)))?;
+
+    search_in(&conn, &search, filters.unwrap_or_default(), limit, offset)
+}
+
+pub const DEFAULT_SEARCH_BATCH: u32 = 200;
+
+#[derive(Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SearchResultPayload {
+    search_id: u32,
+    games: Vec<Game>,
+}
 
-    // normalize input the same way as SQL: lowercase + remove separators/spaces
-    let normalized: String = search
-        .to_lowercase()
+#[derive(Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SearchDonePayload {
+    search_id: u32,
+    total: u64,
+}
+
+/// Like `search_games` without paging: emits `search-result` with each batch of rows as
+/// it's read, then `search-done` with the total, so the first rows render right away.
+/// `search_id` is echoed in both so the UI can drop batches from a search it replaced.
+#[command(rename_all = "camelCase")]
+pub async fn stream_search(
+    search: String,
+    filters: Option<SearchFilters>,
+    search_id: u32,
+    batch_size: Option<u32>,
+    window: Window,
+) -> Result<u64, AppError> {
+    // Off the main thread, or the batches couldn't reach the webview until the end
+    let search_task = tokio::task::spawn_blocking(move || -> Result<u64, AppError> {
+        let conn = db::open()
+            .map_err(|e| AppError::db(format!("Failed to open DB: {}", e)))?;
+
+        let batch_size = batch_size.unwrap_or(DEFAULT_SEARCH_BATCH) as usize;
+        let total = stream_search_in(&conn, &search, &filters.unwrap_or_default(), batch_size, |games| {
+            let _ = window.emit("search-result", SearchResultPayload { search_id, games });
+        })?;
+
+        let _ = window.emit("search-done", SearchDonePayload { search_id, total });
+        Ok(total)
+    });
+
+    search_task.await.map_err(|e| AppError::internal(e.to_string()))?
+}
+
+/// Normalizes a name the same way as the SQL search key: lowercase + remove separators/spaces.
+pub fn normalize_name(name: &str) -> String {
+    name.to_lowercase()
         .chars()
         .filter(|c| !c.is_whitespace() && *c != '-' && *c != '_' && *c != ':')
-        .collect();
+        .collect()
+}
 
-    let pattern = format!("%{}%", normalized);
+/// The search split on whitespace, each keyword normalized like `normalize_name`.
+fn search_keywords(search: &str) -> Vec<String> {
+    search
+        .split_whitespace()
+        .map(normalize_name)
+        .filter(|k| !k.is_empty())
+        .collect()
+}
 
-    let mut stmt = conn
-        .prepare(
-            "SELECT id, name, console, size, dl_link, is_downloaded
-             FROM games
-             WHERE LOWER(REPLACE(REPLACE(REPLACE(REPLACE(name, ' ', ''), '-', ''), '_', ''), ':', '')) LIKE ?
-             LIMIT 200"
+/// Appends the non-name filters (console, size, region, betas, date) to a WHERE clause.
+fn push_filters(filters: &SearchFilters, where_sql: &mut String, params: &mut Vec<Value>) {
+    if let Some(console) = filters.console.clone().filter(|c| !c.trim().is_empty()) {
+        where_sql.push_str(" AND console = ?");
+        params.push(Value::Text(console));
+    }
+    if let Some(min) = filters.min_size {
+        where_sql.push_str(" AND size_bytes >= ?");
+        params.push(Value::Integer(min));
+    }
+    if let Some(max) = filters.max_size {
+        where_sql.push_str(" AND size_bytes <= ?");
+        params.push(Value::Integer(max));
+    }
+
+    // A region can sit alone or anywhere in a list: "(USA)", "(USA, Europe)", "(Japan, USA)"
+    let regions: Vec<&String> = filters.regions.iter().filter(|r| !r.trim().is_empty()).collect();
+    if !regions.is_empty() {
+        let mut clauses = Vec::new();
+        for region in regions {
+            let region = region.trim();
+            for pattern in [
+                format!("%({})%", region),
+                format!("%({},%", region),
+                format!("%, {})%", region),
+                format!("%, {},%", region),
+            ] {
+                clauses.push("name LIKE ?");
+                params.push(Value::Text(pattern));
+            }
+        }
+        where_sql.push_str(&format!(" AND ({})", clauses.join(" OR ")));
+    }
+    // Games whose date couldn't be parsed drop out once either bound is set
+    if let Some(after) = filters.added_after {
+        where_sql.push_str(" AND date_unix >= ?");
+        params.push(Value::Integer(after));
+    }
+    if let Some(before) = filters.added_before {
+        where_sql.push_str(" AND date_unix <= ?");
+        params.push(Value::Integer(before));
+    }
+    if filters.exclude_betas {
+        where_sql.push_str(" AND name NOT LIKE '%(Beta%' AND name NOT LIKE '%(Proto%'");
+    }
+}
+
+// Trigram tokens are three characters; shorter terms can't use the FTS index.
+const FTS_MIN_CHARS: usize = 3;
+
+/// The WHERE and ORDER BY of a non-fuzzy search, with their bound values.
+struct SearchSql {
+    where_sql: String,
+    where_params: Vec<Value>,
+    order_sql: String,
+    order_params: Vec<Value>,
+}
+
+fn uses_fuzzy(search: &str, filters: &SearchFilters) -> bool {
+    filters.fuzzy && normalize_name(search).chars().count() >= FTS_MIN_CHARS
+}
+
+fn search_sql(search: &str, filters: &SearchFilters) -> SearchSql {
+    let mut where_sql = String::from("WHERE 1 = 1");
+    let mut params: Vec<Value> = Vec::new();
+
+    // Every whitespace-separated keyword has to appear somewhere in the name, in any order
+    let keywords = search_keywords(search);
+    let (indexed, short): (Vec<&String>, Vec<&String>) =
+        keywords.iter().partition(|k| k.chars().count() >= FTS_MIN_CHARS);
+
+    if !indexed.is_empty() {
+        // Each keyword quoted as its own FTS phrase: a substring match on the normalized name
+        let query = indexed
+            .iter()
+            .map(|k| format!("\"{}\"", k.replace('"', "\"\"")))
+            .collect::<Vec<_>>()
+            .join(" AND ");
+        where_sql.push_str(" AND id IN (SELECT rowid FROM games_fts WHERE games_fts MATCH ?)");
+        params.push(Value::Text(query));
+    }
+    for keyword in short {
+        where_sql.push_str(&format!(
+            " AND ({} LIKE ? OR {} LIKE ?)",
+            crate::data::search_key("name"),
+            crate::data::search_key("clean_name")
+        ));
+        params.push(Value::Text(format!("%{}%", keyword)));
+        params.push(Value::Text(format!("%{}%", keyword)));
+    }
+
+    push_filters(filters, &mut where_sql, &mut params);
+
+    // Games with every keyword in the primary title rank above ones with some only in a tag
+    let mut order_params = Vec::new();
+    let order_sql = if keywords.is_empty() {
+        "id".to_string()
+    } else {
+        let clean_key = crate::data::search_key("COALESCE(clean_name, name)");
+        let all_in_title = keywords
+            .iter()
+            .map(|k| {
+                order_params.push(Value::Text(format!("%{}%", k)));
+                format!("{} LIKE ?", clean_key)
+            })
+            .collect::<Vec<_>>()
+            .join(" AND ");
+        format!("({}) DESC, id", all_in_title)
+    };
+
+    SearchSql {
+        where_sql,
+        where_params: params,
+        order_sql,
+        order_params,
+    }
+}
+
+fn search_in(
+    conn: &Connection,
+    search: &str,
+    filters: SearchFilters,
+    limit: u32,
+    offset: u32,
+) -> Result<SearchPage, AppError> {
+    if uses_fuzzy(search, &filters) {
+        return fuzzy_search(conn, search, &filters, limit, offset);
+    }
+
+    let SearchSql {
+        where_sql,
+        where_params,
+        order_sql,
+        order_params,
+    } = search_sql(search, &filters);
+
+    let total: i64 = conn
+        .query_row(
+            &format!("SELECT COUNT(*) FROM games {}", where_sql),
+            params_from_iter(where_params.iter()),
+            |row| row.get(0),
         )
-        .map_err(|e| format!("Failed to prepare query: {}", e))?;
+        .map_err(|e| AppError::db(format!("Count query failed: {}", e)))?;
+
+    let sql = format!(
+        "SELECT {}
+         FROM {}
+         {}
+         ORDER BY {}
+         LIMIT ? OFFSET ?",
+        GAME_COLUMNS, GAME_TABLES, where_sql, order_sql
+    );
+    let mut params = where_params;
+    params.extend(order_params);
+    params.push(Value::Integer(limit as i64));
+    params.push(Value::Integer(offset as i64));
+
+    let mut stmt = conn
+        .prepare(&sql)
+        .map_err(|e| AppError::db(format!("Failed to prepare query: {}", e)))?;
 
     let games_iter = stmt
-        .query_map([pattern], |row| {
-            Ok(Game {
-                id: row.get(0)?,
-                name: row.get(1)?,
-                console: row.get(2)?,
-                size: row.get(3)?,
-                dl_link: row.get(4)?,
-                is_downloaded: row.get::<_, i64>(5)? != 0,
-            })
-        })
-        .map_err(|e| format!("Query execution failed: {}", e))?;
+        .query_map(params_from_iter(params), game_from_row)
+        .map_err(|e| AppError::db(format!("Query execution failed: {}", e)))?;
 
     let mut results = Vec::new();
     for game in games_iter {
-        results.push(game.map_err(|e| format!("Row error: {}", e))?);
+        results.push(game.map_err(|e| AppError::db(format!("Row error: {}", e)))?);
+    }
+
+    Ok(SearchPage {
+        games: results,
+        total: total as u64,
+    })
+}
+
+#[command]
+pub fn list_consoles() -> Result<Vec<String>, AppError> {
+    let conn = db::open()
+        .map_err(|e| AppError::db(format!("Failed to open DB: {}", e)))?;
+
+    let mut stmt = conn
+        .prepare("SELECT DISTINCT console FROM consoles ORDER BY console")
+        .map_err(|e| AppError::db(format!("Failed to prepare query: {}", e)))?;
+
+    let consoles = stmt
+        .query_map([], |row| row.get::<_, String>(0))
+        .map_err(|e| AppError::db(format!("Query execution failed: {}", e)))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| AppError::db(format!("Row error: {}", e)))?;
+
+    Ok(consoles)
+}
+
+// Closest-match score (0..1) a name needs to show up in fuzzy results
+const FUZZY_THRESHOLD: f64 = 0.75;
+// Best-ranked rows pulled by the coarse pre-filter before scoring
+const FUZZY_CANDIDATES: i64 = 5000;
+
+/// `normalize_name` plus accent folding, so "Pokémon" and "pokemon" compare equal.
+fn fuzzy_key(name: &str) -> String {
+    normalize_name(name)
+        .nfd()
+        .filter(|c| !is_combining_mark(*c))
+        .collect()
+}
+
+/// Best similarity between `query` and any same-length-ish window of `key`.
+fn fuzzy_score(query: &[char], key: &[char]) -> f64 {
+    if key.len() <= query.len() {
+        let key: String = key.iter().collect();
+        let query: String = query.iter().collect();
+        return normalized_damerau_levenshtein(&query, &key);
+    }
+
+    let query_str: String = query.iter().collect();
+    let mut best: f64 = 0.0;
+    // One char of slack either way covers a dropped or doubled letter
+    for width in [query.len().saturating_sub(1), query.len(), query.len() + 1] {
+        if width == 0 || width > key.len() {
+            continue;
+        }
+        for window in key.windows(width) {
+            let window: String = window.iter().collect();
+            best = best.max(normalized_damerau_levenshtein(&query_str, &window));
+        }
+    }
+    best
+}
+
+/// Runs a search in the same order as `search_in`, handing over rows `batch_size` at a
+/// time as they're read instead of collecting a page first. Returns how many were sent.
+/// Fuzzy matches have to be scored before they can be ranked, so those come in batches
+/// only once ranking is done.
+fn stream_search_in(
+    conn: &Connection,
+    search: &str,
+    filters: &SearchFilters,
+    batch_size: usize,
+    mut on_batch: impl FnMut(Vec<Game>),
+) -> Result<u64, AppError> {
+    let batch_size = batch_size.max(1);
+
+    if uses_fuzzy(search, filters) {
+        let page = fuzzy_search(conn, search, filters, u32::MAX, 0)?;
+        let mut games = page.games.into_iter().peekable();
+        while games.peek().is_some() {
+            on_batch(games.by_ref().take(batch_size).collect());
+        }
+        return Ok(page.total);
+    }
+
+    let SearchSql {
+        where_sql,
+        mut where_params,
+        order_sql,
+        order_params,
+    } = search_sql(search, filters);
+    where_params.extend(order_params);
+
+    let sql = format!(
+        "SELECT {} FROM {} {} ORDER BY {}",
+        GAME_COLUMNS, GAME_TABLES, where_sql, order_sql
+    );
+    let mut stmt = conn
+        .prepare(&sql)
+        .map_err(|e| AppError::db(format!("Failed to prepare query: {}", e)))?;
+    let rows = stmt
+        .query_map(params_from_iter(where_params), game_from_row)
+        .map_err(|e| AppError::db(format!("Query execution failed: {}", e)))?;
+
+    let mut total = 0u64;
+    let mut batch = Vec::with_capacity(batch_size);
+    for game in rows {
+        batch.push(game.map_err(|e| AppError::db(format!("Row error: {}", e)))?);
+        total += 1;
+        if batch.len() == batch_size {
+            on_batch(std::mem::replace(&mut batch, Vec::with_capacity(batch_size)));
+        }
+    }
+    if !batch.is_empty() {
+        on_batch(batch);
     }
 
-    Ok(results)
+    Ok(total)
 }
 
+/// Typo-tolerant search: pull the rows sharing the most (and rarest) trigrams with the
+/// query through the FTS index, then rank them by edit distance. Only the best
+/// `FUZZY_CANDIDATES` are scored, so `total` is exact unless more than that many match.
+fn fuzzy_search(
+    conn: &Connection,
+    search: &str,
+    filters: &SearchFilters,
+    limit: u32,
+    offset: u32,
+) -> Result<SearchPage, AppError> {
+    let query: Vec<char> = fuzzy_key(search).chars().collect();
 
+    let trigrams: Vec<String> = query
+        .windows(FTS_MIN_CHARS)
+        .map(|w| format!("\"{}\"", w.iter().collect::<String>().replace('"', "\"\"")))
+        .collect();
+
+    // bm25 over an OR of trigrams ranks rows matching more of them first, so the
+    // candidate limit cuts off the weakest matches rather than the highest ids
+    let mut where_sql = String::from("WHERE 1 = 1");
+    let mut params: Vec<Value> = vec![Value::Text(trigrams.join(" OR "))];
+    push_filters(filters, &mut where_sql, &mut params);
+    params.push(Value::Integer(FUZZY_CANDIDATES));
+
+    let sql = format!(
+        "SELECT {} FROM {}
+         JOIN (SELECT rowid AS fts_id, rank AS fts_rank FROM games_fts WHERE games_fts MATCH ?) m
+           ON m.fts_id = games.id
+         {} ORDER BY m.fts_rank, id LIMIT ?",
+        GAME_COLUMNS, GAME_TABLES, where_sql
+    );
+    let mut stmt = conn
+        .prepare(&sql)
+        .map_err(|e| AppError::db(format!("Failed to prepare query: {}", e)))?;
+
+    let candidates = stmt
+        .query_map(params_from_iter(params), game_from_row)
+        .map_err(|e| AppError::db(format!("Query execution failed: {}", e)))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| AppError::db(format!("Row error: {}", e)))?;
+
+    let mut scored: Vec<(f64, Game)> = candidates
+        .into_iter()
+        .map(|game| {
+            let key: Vec<char> = fuzzy_key(&game.name).chars().collect();
+            (fuzzy_score(&query, &key), game)
+        })
+        .filter(|(score, _)| *score >= FUZZY_THRESHOLD)
+        .collect();
+
+    // Best match first; ties keep catalog order
+    scored.sort_by(|a, b| b.0.total_cmp(&a.0).then(a.1.id.cmp(&b.1.id)));
+
+    let total = scored.len() as u64;
+    let games = scored
+        .into_iter()
+        .skip(offset as usize)
+        .take(limit as usize)
+        .map(|(_, game)| game)
+        .collect();
+
+    Ok(SearchPage { games, total })
+}
+
+#[derive(serde::Serialize)]
+pub struct ConsoleStat {
+    pub console: String,
+    pub game_count: u64,
+    pub total_size_bytes: u64,
+    pub downloaded_count: u64,
+}
+
+#[command]
+pub fn console_stats() -> Result<Vec<ConsoleStat>, AppError> {
+    let conn = db::open()
+        .map_err(|e| AppError::db(format!("Failed to open DB: {}", e)))?;
+
+    // A DB that hasn't been migrated yet has no sizes to sum
+    let has_sizes = crate::data::has_column(&conn, "games", "size_bytes")
+        .map_err(|e| AppError::db(format!("Failed to read games schema: {}", e)))?;
+    let size_sql = if has_sizes { "COALESCE(SUM(size_bytes), 0)" } else { "0" };
+
+    let sql = format!(
+        "SELECT console, COUNT(*), {}, SUM(CASE WHEN is_downloaded THEN 1 ELSE 0 END)
+         FROM games
+         GROUP BY console
+         ORDER BY console",
+        size_sql
+    );
+
+    let mut stmt = conn
+        .prepare(&sql)
+        .map_err(|e| AppError::db(format!("Failed to prepare query: {}", e)))?;
+
+    let stats = stmt
+        .query_map([], |row| {
+            Ok(ConsoleStat {
+                console: row.get(0)?,
+                game_count: row.get::<_, i64>(1)? as u64,
+                total_size_bytes: row.get::<_, i64>(2)? as u64,
+                downloaded_count: row.get::<_, i64>(3)? as u64,
+            })
+        })
+        .map_err(|e| AppError::db(format!("Query execution failed: {}", e)))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| AppError::db(format!("Row error: {}", e)))?;
+
+    Ok(stats)
+}
+
+/// Games a scrape inserted after `since` (unix seconds), newest first, e.g. to list
+/// what an incremental re-scrape found.
+#[command]
+pub fn recently_added(since: i64, limit: u32) -> Result<Vec<Game>, AppError> {
+    let conn = db::open()
+        .map_err(|e| AppError::db(format!("Failed to open DB: {}", e)))?;
+    recently_added_in(&conn, since, limit)
+}
+
+fn recently_added_in(conn: &Connection, since: i64, limit: u32) -> Result<Vec<Game>, AppError> {
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {} FROM {} WHERE first_seen > ?1 ORDER BY first_seen DESC, id DESC LIMIT ?2",
+            GAME_COLUMNS, GAME_TABLES
+        ))
+        .map_err(|e| AppError::db(format!("Failed to prepare query: {}", e)))?;
+
+    let games = stmt
+        .query_map(rusqlite::params![since, limit as i64], game_from_row)
+        .map_err(|e| AppError::db(format!("Query execution failed: {}", e)))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| AppError::db(format!("Row error: {}", e)))?;
+
+    Ok(games)
+}
+
+#[command]
+pub fn get_game(id: i64) -> Result<Option<Game>, AppError> {
+    let conn = db::open()
+        .map_err(|e| AppError::db(format!("Failed to open DB: {}", e)))?;
+
+    let game = conn.query_row(
+        &format!("SELECT {} FROM {} WHERE id = ?1", GAME_COLUMNS, GAME_TABLES),
+        [id],
+        game_from_row,
+    );
+
+    match game {
+        Ok(g) => Ok(Some(g)),
+        Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+        Err(e) => Err(AppError::db(format!("Query execution failed: {}", e))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    fn test_db(names: &[String]) -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::data::init_schema(&conn).unwrap();
+
+        let tx = conn.unchecked_transaction().unwrap();
+        for name in names {
+            tx.execute(
+                "INSERT INTO games (name, console, date, size, dl_link, is_downloaded, clean_name)
+                 VALUES (?1, 'Test', '', '', '', 0, ?2)",
+                [name, &crate::data::clean_name(name)],
+            )
+            .unwrap();
+        }
+        tx.commit().unwrap();
+        conn
+    }
+
+    fn names(page: &SearchPage) -> Vec<&str> {
+        page.games.iter().map(|g| g.name.as_str()).collect()
+    }
+
+    fn search(conn: &Connection, term: &str) -> SearchPage {
+        search_in(conn, term, SearchFilters::default(), 100, 0).unwrap()
+    }
+
+    #[test]
+    fn fts_search_ignores_separators_and_case() {
+        let conn = test_db(&[
+            "Super Mario Bros. (USA)".to_string(),
+            "Mario Kart - Super Circuit (Europe)".to_string(),
+            "Zelda: Link's Awakening (USA)".to_string(),
+        ]);
+
+        assert_eq!(names(&search(&conn, "supermario")), ["Super Mario Bros. (USA)"]);
+        assert_eq!(names(&search(&conn, "MARIO")).len(), 2);
+        assert_eq!(names(&search(&conn, "zelda link")), ["Zelda: Link's Awakening (USA)"]);
+        assert_eq!(names(&search(&conn, "kart-super")), ["Mario Kart - Super Circuit (Europe)"]);
+        // Short terms fall back to LIKE
+        assert_eq!(names(&search(&conn, "ze")), ["Zelda: Link's Awakening (USA)"]);
+        assert_eq!(search(&conn, "").total, 3);
+    }
+
+    #[test]
+    fn fuzzy_search_tolerates_typos_and_accents() {
+        let conn = test_db(&[
+            "Pokémon Red Version (USA)".to_string(),
+            "Super Mario Land (World)".to_string(),
+            "Tetris (World)".to_string(),
+        ]);
+        let fuzzy = |term: &str| {
+            let filters = SearchFilters {
+                fuzzy: true,
+                ..Default::default()
+            };
+            search_in(&conn, term, filters, 100, 0).unwrap()
+        };
+
+        assert_eq!(names(&fuzzy("pokmon")), ["Pokémon Red Version (USA)"]);
+        assert_eq!(names(&fuzzy("mraio land")), ["Super Mario Land (World)"]);
+        assert_eq!(fuzzy("zelda").total, 0);
+    }
+
+    #[test]
+    fn fuzzy_candidates_are_the_best_trigram_matches() {
+        // Far more rows share a trigram with the query than the pre-filter keeps;
+        // the close match has the highest id but still has to be found
+        let mut rows: Vec<String> = (0..FUZZY_CANDIDATES + 500).map(|i| format!("Mario Party {}", i)).collect();
+        rows.push("Super Mario Land (World)".to_string());
+        let conn = test_db(&rows);
+
+        let filters = SearchFilters {
+            fuzzy: true,
+            ..Default::default()
+        };
+        let page = search_in(&conn, "super mraio land", filters, 100, 0).unwrap();
+        assert_eq!(names(&page).first().copied(), Some("Super Mario Land (World)"));
+    }
+
+    #[test]
+    fn primary_title_matches_rank_first() {
+        let conn = test_db(&[
+            "Link's Adventure (Zelda Hack) (USA).zip".to_string(),
+            "Zelda (USA) II - The Adventure of Link.zip".to_string(),
+            "Legend of Zelda, The (USA) [b].zip".to_string(),
+        ]);
+
+        assert_eq!(
+            names(&search(&conn, "zelda")),
+            [
+                "Zelda (USA) II - The Adventure of Link.zip",
+                "Legend of Zelda, The (USA) [b].zip",
+                "Link's Adventure (Zelda Hack) (USA).zip",
+            ]
+        );
+        // Only the clean title has these words side by side
+        assert_eq!(names(&search(&conn, "zelda ii")), ["Zelda (USA) II - The Adventure of Link.zip"]);
+        assert_eq!(crate::data::clean_name("Legend of Zelda, The (USA) [b].zip"), "Legend of Zelda, The");
+    }
+
+    #[test]
+    fn keywords_match_in_any_order() {
+        let conn = test_db(&[
+            "Legend of Zelda, The - Majora's Mask (USA).zip".to_string(),
+            "Legend of Zelda, The - Majora's Mask (Europe).zip".to_string(),
+            "Legend of Zelda, The - Ocarina of Time (USA).zip".to_string(),
+        ]);
+
+        assert_eq!(
+            names(&search(&conn, "zelda majora usa")),
+            ["Legend of Zelda, The - Majora's Mask (USA).zip"]
+        );
+        assert_eq!(search(&conn, "usa zelda").total, 2);
+        // Short keywords go through LIKE but still have to match
+        assert_eq!(search(&conn, "mask eu").total, 1);
+    }
+
+    #[test]
+    fn date_filters_use_the_parsed_listing_date() {
+        use crate::data::parse_listing_date;
+
+        assert_eq!(parse_listing_date("07-Dec-2023 01:19"), Some(1_701_911_940));
+        assert_eq!(parse_listing_date("2023-Dec-07 01:19"), Some(1_701_911_940));
+        assert_eq!(parse_listing_date("2023-12-07 01:19:00"), Some(1_701_911_940));
+        assert_eq!(parse_listing_date("Unknown"), None);
+
+        let conn = test_db(&["Old Game".to_string(), "New Game".to_string(), "Undated".to_string()]);
+        for (name, date) in [("Old Game", "01-Jan-2020 00:00"), ("New Game", "07-Dec-2023 01:19")] {
+            conn.execute(
+                "UPDATE games SET date_unix = ?1 WHERE name = ?2",
+                rusqlite::params![parse_listing_date(date), name],
+            )
+            .unwrap();
+        }
+
+        let added = |after: Option<i64>, before: Option<i64>| {
+            let filters = SearchFilters {
+                added_after: after,
+                added_before: before,
+                ..Default::default()
+            };
+            search_in(&conn, "", filters, 100, 0).unwrap()
+        };
+        assert_eq!(names(&added(Some(1_700_000_000), None)), ["New Game"]);
+        assert_eq!(names(&added(None, Some(1_700_000_000))), ["Old Game"]);
+    }
+
+    #[test]
+    fn recently_added_skips_rows_seen_before() {
+        let conn = test_db(&["Old".to_string(), "Newer".to_string(), "Newest".to_string()]);
+        for (name, seen) in [("Old", 100), ("Newer", 200), ("Newest", 300)] {
+            conn.execute(
+                "UPDATE games SET first_seen = ?1 WHERE name = ?2",
+                rusqlite::params![seen, name],
+            )
+            .unwrap();
+        }
+
+        let added = recently_added_in(&conn, 100, 10).unwrap();
+        let added: Vec<&str> = added.iter().map(|g| g.name.as_str()).collect();
+        assert_eq!(added, ["Newest", "Newer"]);
+        assert_eq!(recently_added_in(&conn, 100, 1).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn regions_are_counted_per_region() {
+        let conn = test_db(&[
+            "Tetris (World)".to_string(),
+            "Pokemon Red (USA, Europe)".to_string(),
+            "Sonic (USA)".to_string(),
+            "Homebrew Demo (PD)".to_string(),
+        ]);
+
+        assert_eq!(
+            regions_in(&conn).unwrap(),
+            [
+                ("Europe".to_string(), 1),
+                ("USA".to_string(), 2),
+                ("World".to_string(), 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn fts_index_follows_renames_and_deletes() {
+        let conn = test_db(&["Tetris.zip".to_string(), "Pong".to_string()]);
+
+        conn.execute("UPDATE games SET name = 'Tetris DX' WHERE name = 'Tetris.zip'", [])
+            .unwrap();
+        conn.execute("DELETE FROM games WHERE name = 'Pong'", []).unwrap();
+
+        assert_eq!(names(&search(&conn, "tetrisdx")), ["Tetris DX"]);
+        assert_eq!(search(&conn, "pong").total, 0);
+    }
+
+    fn query_plan(conn: &Connection, sql: &str) -> String {
+        let mut stmt = conn.prepare(&format!("EXPLAIN QUERY PLAN {}", sql)).unwrap();
+        stmt.query_map([], |row| row.get::<_, String>(3))
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap()
+            .join("\n")
+    }
+
+    #[test]
+    fn console_and_title_lookups_use_indexes() {
+        let conn = test_db(&["Tetris (World)".to_string()]);
+
+        let by_console = query_plan(&conn, "SELECT id FROM games WHERE console = 'Test' ORDER BY id");
+        assert!(by_console.contains("idx_games_console"), "{}", by_console);
+
+        let by_title = query_plan(&conn, "SELECT id FROM games WHERE clean_name = 'Tetris'");
+        assert!(by_title.contains("idx_games_clean_name"), "{}", by_title);
+    }
+
+    #[test]
+    fn streamed_batches_match_the_paged_search() {
+        let rows: Vec<String> = (0..25).map(|i| format!("Mario Game {} (USA)", i)).collect();
+        let conn = test_db(&rows);
+
+        let mut batches = Vec::new();
+        let total = stream_search_in(&conn, "mario", &SearchFilters::default(), 10, |games| {
+            batches.push(games.into_iter().map(|g| g.name).collect::<Vec<_>>());
+        })
+        .unwrap();
+
+        assert_eq!(total, 25);
+        assert_eq!(batches.iter().map(Vec::len).collect::<Vec<_>>(), [10, 10, 5]);
+        let paged = search(&conn, "mario");
+        assert_eq!(batches.concat(), names(&paged));
+    }
+
+    // cargo test --release -- --ignored --nocapture fts_benchmark
+    #[test]
+    #[ignore]
+    fn fts_benchmark_50k() {
+        let rows: Vec<String> = (0..50_000)
+            .map(|i| format!("Game Title {} - Part {} (USA)", i, i % 97))
+            .collect();
+        let conn = test_db(&rows);
+
+        let like_sql = format!(
+            "SELECT COUNT(*) FROM games WHERE {} LIKE ?",
+            crate::data::search_key("name")
+        );
+        let start = Instant::now();
+        let like_total: i64 = conn
+            .query_row(&like_sql, ["%title4999%"], |row| row.get(0))
+            .unwrap();
+        let like_time = start.elapsed();
+
+        let start = Instant::now();
+        let fts_total = search(&conn, "title 4999").total;
+        let fts_time = start.elapsed();
+
+        println!("LIKE: {} rows in {:?}, FTS: {} rows in {:?}", like_total, like_time, fts_total, fts_time);
+        assert_eq!(like_total as u64, fts_total);
+    }
+}
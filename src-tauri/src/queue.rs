@@ -0,0 +1,452 @@
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::{Condvar, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use serde::Serialize;
+use tauri::{Emitter, Window};
+use tracing::info;
+
+use crate::error::AppError;
+use crate::history::DownloadState;
+use crate::settings::read_u64_setting;
+
+pub const DEFAULT_MAX_CONCURRENT_DOWNLOADS: u64 = 3;
+
+struct Slots {
+    active: usize,     // downloads currently holding a slot
+    waiting: Vec<u32>, // ids waiting for one, next to start first
+}
+
+// Everything without a slot waits on `SLOT_FREED` and starts in `waiting` order.
+static SLOTS: Mutex<Slots> = Mutex::new(Slots { active: 0, waiting: Vec::new() });
+static SLOT_FREED: Condvar = Condvar::new();
+
+pub(crate) fn max_concurrent() -> usize {
+    read_u64_setting("max_concurrent_downloads", DEFAULT_MAX_CONCURRENT_DOWNLOADS)
+        .unwrap_or(DEFAULT_MAX_CONCURRENT_DOWNLOADS)
+        .max(1) as usize
+}
+
+/// Held for the lifetime of one running download; dropping it lets the next one start.
+pub struct DownloadSlot(());
+
+impl Drop for DownloadSlot {
+    fn drop(&mut self) {
+        let mut slots = SLOTS.lock().unwrap_or_else(|e| e.into_inner());
+        slots.active = slots.active.saturating_sub(1);
+        SLOT_FREED.notify_all();
+    }
+}
+
+/// Blocks until download `id` is among the next to start, fewer than
+/// `max_concurrent_downloads` are running and the queue isn't paused.
+/// `None` if it was cancelled while it waited.
+pub fn acquire_slot(id: u32) -> Option<DownloadSlot> {
+    SLOTS.lock().unwrap_or_else(|e| e.into_inner()).waiting.push(id);
+    loop {
+        // Read from the DB before taking the lock, so queue reads and moves don't wait on it
+        let limit = max_concurrent();
+        let mut slots = SLOTS.lock().unwrap_or_else(|e| e.into_inner());
+        if is_cancelled(id) || is_dequeued(id) {
+            slots.waiting.retain(|&w| w != id);
+            return None;
+        }
+        let free = limit.saturating_sub(slots.active);
+        let position = slots.waiting.iter().position(|&w| w == id).unwrap_or(0);
+        if !pause_state().paused && position < free {
+            slots.waiting.remove(position);
+            slots.active += 1;
+            return Some(DownloadSlot(()));
+        }
+        // Wake up now and then so a raised limit takes effect without a finished download
+        let _ = SLOT_FREED
+            .wait_timeout(slots, Duration::from_secs(1))
+            .unwrap_or_else(|e| e.into_inner());
+    }
+}
+
+/// Ids waiting for a slot, in the order they'll start.
+#[tauri::command]
+pub fn get_download_queue() -> Vec<u32> {
+    SLOTS.lock().unwrap_or_else(|e| e.into_inner()).waiting.clone()
+}
+
+/// Moves a waiting download to `position` (0 = next to start; past the end = last)
+/// and emits the new order as `download-queue`.
+#[tauri::command]
+pub fn move_in_queue(id: u32, position: u32, window: Window) -> Result<Vec<u32>, AppError> {
+    let order = {
+        let mut slots = SLOTS.lock().unwrap_or_else(|e| e.into_inner());
+        let from = slots
+            .waiting
+            .iter()
+            .position(|&w| w == id)
+            .ok_or_else(|| AppError::not_found(format!("Download {} isn't waiting in the queue", id)))?;
+        slots.waiting.remove(from);
+        let to = (position as usize).min(slots.waiting.len());
+        slots.waiting.insert(to, id);
+        slots.waiting.clone()
+    };
+    // A download moved into the startable range may be able to go now
+    SLOT_FREED.notify_all();
+
+    let _ = window.emit("download-queue", &order);
+    Ok(order)
+}
+
+/// Jumps a waiting download to the front of the queue.
+#[tauri::command]
+pub fn prioritize_download(id: u32, window: Window) -> Result<Vec<u32>, AppError> {
+    move_in_queue(id, 0, window)
+}
+
+#[derive(Serialize, Clone, Debug)]
+struct DownloadDequeuedPayload {
+    id: u32,
+}
+
+/// Takes a download that's still waiting for a slot out of the queue; running ones are
+/// left alone (see `cancel_all_downloads` for those). Emits `download-dequeued` and the
+/// new order as `download-queue`.
+#[tauri::command]
+pub fn dequeue_download(id: u32, window: Window) -> Result<Vec<u32>, AppError> {
+    let order = {
+        let mut slots = SLOTS.lock().unwrap_or_else(|e| e.into_inner());
+        let position = slots
+            .waiting
+            .iter()
+            .position(|&w| w == id)
+            .ok_or_else(|| AppError::not_found(format!("Download {} isn't waiting in the queue", id)))?;
+        slots.waiting.remove(position);
+        with_tracked(id, |t| t.dequeued = true);
+        slots.waiting.clone()
+    };
+    // Wake its acquire_slot so the waiting thread gives up
+    SLOT_FREED.notify_all();
+
+    info!("Download {} removed from the queue", id);
+    let _ = window.emit("download-dequeued", DownloadDequeuedPayload { id });
+    let _ = window.emit("download-queue", &order);
+    Ok(order)
+}
+
+// ------------------------ Queue pause ------------------------
+
+#[derive(Serialize, Clone, Copy, Debug, Default)]
+pub struct QueueState {
+    pub paused: bool,
+    pub pause_running: bool, // running downloads are held too, not just queued ones
+}
+
+static PAUSE: Mutex<QueueState> = Mutex::new(QueueState { paused: false, pause_running: false });
+static PAUSE_CHANGED: Condvar = Condvar::new();
+
+pub fn pause_state() -> QueueState {
+    *PAUSE.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Stops queued downloads from starting; with `pause_running`, running ones stop
+/// reading at their next `wait_while_paused` too.
+pub fn pause(pause_running: bool) -> QueueState {
+    let mut state = PAUSE.lock().unwrap_or_else(|e| e.into_inner());
+    *state = QueueState { paused: true, pause_running };
+    PAUSE_CHANGED.notify_all();
+    *state
+}
+
+pub fn resume() -> QueueState {
+    let state = {
+        let mut state = PAUSE.lock().unwrap_or_else(|e| e.into_inner());
+        *state = QueueState::default();
+        PAUSE_CHANGED.notify_all();
+        *state
+    };
+    // Let queued downloads take the free slots right away
+    SLOT_FREED.notify_all();
+    state
+}
+
+/// Called by the download loops between reads: blocks while the whole queue is
+/// paused, returning early if `id` gets cancelled meanwhile.
+pub fn wait_while_paused(id: u32) {
+    let mut state = PAUSE.lock().unwrap_or_else(|e| e.into_inner());
+    while state.paused && state.pause_running {
+        // cancel_all notifies PAUSE_CHANGED, so this sees a cancel right away
+        if is_cancelled(id) {
+            return;
+        }
+        state = PAUSE_CHANGED
+            .wait_timeout(state, Duration::from_secs(1))
+            .unwrap_or_else(|e| e.into_inner())
+            .0;
+    }
+}
+
+#[tauri::command]
+pub fn get_queue_state() -> QueueState {
+    pause_state()
+}
+
+/// Master switch: queued downloads wait until `resume_queue`. With `pause_running`
+/// the running ones stop reading too; their connections are held open, so a long
+/// pause may cost a single-stream download its connection.
+#[tauri::command(rename_all = "camelCase")]
+pub fn pause_queue(pause_running: Option<bool>, window: Window) -> QueueState {
+    let state = pause(pause_running.unwrap_or(false));
+    info!("Download queue paused (running downloads too: {})", state.pause_running);
+    let _ = window.emit("queue-state", state);
+    state
+}
+
+#[tauri::command]
+pub fn resume_queue(window: Window) -> QueueState {
+    let state = resume();
+    info!("Download queue resumed");
+    let _ = window.emit("queue-state", state);
+    state
+}
+
+// ------------------------ Active download registry ------------------------
+
+// Gap between throughput samples, and how many are kept (5 minutes' worth)
+const SPEED_SAMPLE_EVERY: Duration = Duration::from_secs(1);
+const MAX_SPEED_SAMPLES: usize = 300;
+
+struct Tracked {
+    state: DownloadState,
+    downloaded: u64,
+    total: u64,
+    started: Option<Instant>, // set once bytes start flowing
+    cancelled: bool,
+    dequeued: bool, // taken out of the queue before it started
+    last_sample: Option<(Instant, u64)>, // when the last sample was taken and the byte count then
+    samples: VecDeque<(u64, u64)>,       // (unix ms, bytes/sec), oldest first
+}
+
+// Every download between `track` and `untrack`, so the UI can rebuild its view
+static REGISTRY: Mutex<BTreeMap<u32, Tracked>> = Mutex::new(BTreeMap::new());
+
+fn with_tracked(id: u32, f: impl FnOnce(&mut Tracked)) {
+    let mut registry = REGISTRY.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(tracked) = registry.get_mut(&id) {
+        f(tracked);
+    }
+}
+
+/// Registers download `id` as queued. `false` if it's already tracked, i.e. another
+/// download of the same game is queued or running.
+pub fn track(id: u32) -> bool {
+    let mut registry = REGISTRY.lock().unwrap_or_else(|e| e.into_inner());
+    if registry.contains_key(&id) {
+        return false;
+    }
+    registry.insert(
+        id,
+        Tracked {
+            state: DownloadState::Queued,
+            downloaded: 0,
+            total: 0,
+            started: None,
+            cancelled: false,
+            dequeued: false,
+            last_sample: None,
+            samples: VecDeque::new(),
+        },
+    );
+    true
+}
+
+/// Whether download `id` is queued or running.
+pub fn is_tracked(id: u32) -> bool {
+    REGISTRY.lock().unwrap_or_else(|e| e.into_inner()).contains_key(&id)
+}
+
+/// Downloads between `track` and `untrack`, queued or running.
+pub fn tracked_count() -> usize {
+    REGISTRY.lock().unwrap_or_else(|e| e.into_inner()).len()
+}
+
+pub fn untrack(id: u32) {
+    let mut registry = REGISTRY.lock().unwrap_or_else(|e| e.into_inner());
+    registry.remove(&id);
+}
+
+pub fn set_state(id: u32, state: DownloadState) {
+    with_tracked(id, |t| t.state = state);
+}
+
+/// `total` is 0 when the size is unknown.
+pub fn set_bytes(id: u32, downloaded: u64, total: u64) {
+    with_tracked(id, |t| {
+        let now = Instant::now();
+        t.started.get_or_insert(now);
+        t.downloaded = downloaded;
+        t.total = total;
+
+        let (sampled_at, sampled_bytes) = *t.last_sample.get_or_insert((now, downloaded));
+        let elapsed = now.duration_since(sampled_at);
+        if elapsed >= SPEED_SAMPLE_EVERY {
+            let bytes_per_sec = (downloaded.saturating_sub(sampled_bytes) as f64 / elapsed.as_secs_f64()) as u64;
+            if t.samples.len() == MAX_SPEED_SAMPLES {
+                t.samples.pop_front();
+            }
+            t.samples.push_back((unix_millis(), bytes_per_sec));
+            t.last_sample = Some((now, downloaded));
+        }
+    });
+}
+
+fn unix_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// Throughput of download `id` over its last few minutes as `(unix ms, bytes/sec)`
+/// points, one per second, oldest first. Empty once the download is no longer tracked.
+#[tauri::command]
+pub fn get_speed_samples(id: u32) -> Vec<(u64, u64)> {
+    let registry = REGISTRY.lock().unwrap_or_else(|e| e.into_inner());
+    registry
+        .get(&id)
+        .map(|t| t.samples.iter().copied().collect())
+        .unwrap_or_default()
+}
+
+/// Checked by the download loops between reads.
+pub fn is_cancelled(id: u32) -> bool {
+    let registry = REGISTRY.lock().unwrap_or_else(|e| e.into_inner());
+    registry.get(&id).is_some_and(|t| t.cancelled)
+}
+
+/// Whether download `id` was taken out of the queue with `dequeue_download`.
+pub fn is_dequeued(id: u32) -> bool {
+    let registry = REGISTRY.lock().unwrap_or_else(|e| e.into_inner());
+    registry.get(&id).is_some_and(|t| t.dequeued)
+}
+
+/// Flags every tracked download, queued or running, to stop. Returns their ids.
+pub fn cancel_all() -> Vec<u32> {
+    let ids = {
+        let mut registry = REGISTRY.lock().unwrap_or_else(|e| e.into_inner());
+        registry
+            .iter_mut()
+            .filter(|(_, t)| !t.cancelled)
+            .map(|(&id, t)| {
+                t.cancelled = true;
+                id
+            })
+            .collect()
+    };
+    // Wake the queued and paused ones so they drop out instead of carrying on
+    SLOT_FREED.notify_all();
+    PAUSE_CHANGED.notify_all();
+    ids
+}
+
+/// Waits until none of `ids` is tracked any more, or `timeout` passes.
+/// Returns whether they all finished.
+pub fn wait_untracked(ids: &[u32], timeout: Duration) -> bool {
+    let deadline = Instant::now() + timeout;
+    loop {
+        {
+            let registry = REGISTRY.lock().unwrap_or_else(|e| e.into_inner());
+            if ids.iter().all(|id| !registry.contains_key(id)) {
+                return true;
+            }
+        }
+        if Instant::now() >= deadline {
+            return false;
+        }
+        std::thread::sleep(Duration::from_millis(50));
+    }
+}
+
+/// State, bytes downloaded and total size (0 if unknown) of whichever of `ids` are tracked.
+pub fn progress_of(ids: &[u32]) -> BTreeMap<u32, (DownloadState, u64, u64)> {
+    let registry = REGISTRY.lock().unwrap_or_else(|e| e.into_inner());
+    ids.iter()
+        .filter_map(|id| registry.get(id).map(|t| (*id, (t.state, t.downloaded, t.total))))
+        .collect()
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct ActiveDownload {
+    pub id: u32,
+    pub percent: Option<f64>, // None while queued or when the size is unknown
+    pub state: String,
+    pub bytes_per_sec: u64,  // average since the download started
+}
+
+/// Snapshot of queued and running downloads, for rebuilding progress bars after navigation.
+#[tauri::command]
+pub fn get_active_downloads() -> Vec<ActiveDownload> {
+    let registry = REGISTRY.lock().unwrap_or_else(|e| e.into_inner());
+
+    registry
+        .iter()
+        .map(|(&id, t)| {
+            let elapsed = t.started.map(|s| s.elapsed().as_secs_f64()).unwrap_or(0.0);
+            ActiveDownload {
+                id,
+                percent: (t.total > 0).then(|| (t.downloaded as f64 / t.total as f64) * 100.0),
+                state: t.state.as_str().to_string(),
+                bytes_per_sec: if elapsed > 0.0 {
+                    (t.downloaded as f64 / elapsed) as u64
+                } else {
+                    0
+                },
+            }
+        })
+        .collect()
+}
+
+// ------------------------ Destination claims ------------------------
+
+// Archive paths owned by running downloads
+static CLAIMED: Mutex<BTreeSet<PathBuf>> = Mutex::new(BTreeSet::new());
+
+/// Reserves an archive path for one download; released on drop.
+pub struct PathClaim {
+    path: PathBuf,
+}
+
+impl PathClaim {
+    pub fn file_name(&self) -> String {
+        self.path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default()
+    }
+}
+
+impl Drop for PathClaim {
+    fn drop(&mut self) {
+        let mut claimed = CLAIMED.lock().unwrap_or_else(|e| e.into_inner());
+        claimed.remove(&self.path);
+    }
+}
+
+/// Claims `dir/file_name`, or "name (1).ext", "name (2).ext", … if another
+/// running download already holds it. `ext` may be compound, e.g. "nkit.iso".
+pub fn claim_path(dir: &Path, file_name: &str, ext: &str) -> PathClaim {
+    let suffix = format!(".{}", ext);
+    let stem = if file_name.to_ascii_lowercase().ends_with(&suffix) {
+        &file_name[..file_name.len() - suffix.len()]
+    } else {
+        file_name
+    };
+
+    let mut claimed = CLAIMED.lock().unwrap_or_else(|e| e.into_inner());
+    let mut path = dir.join(file_name);
+    let mut n = 1;
+    while claimed.contains(&path) {
+        path = dir.join(format!("{} ({}){}", stem, n, &file_name[stem.len()..]));
+        n += 1;
+    }
+
+    claimed.insert(path.clone());
+    PathClaim { path }
+}
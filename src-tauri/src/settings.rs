@@ -1,47 +1,113 @@
 use rusqlite::Connection;
-use std::path::PathBuf;
+use std::path::Path;
 
 use tauri::Window;
 use tauri_plugin_dialog::{DialogExt, FilePath};
+use tracing::info;
 
-use crate::query::db_path;
-
-fn default_download_dir() -> Result<std::path::PathBuf, String> {
-    let mut p = dirs::home_dir().ok_or("...")?;
-    p.push("Downloads");
-    p.push("Roms");
-    Ok(p)
-}
+use crate::data::{DEFAULT_SCRAPE_DELAY_MS, DEFAULT_SCRAPE_RETRIES, DEFAULT_SCRAPE_RETRY_BACKOFF_MS};
+use crate::download::{
+    DEFAULT_DOWNLOAD_BUFFER_KB, DEFAULT_MAX_DOWNLOAD_CHUNKS, DEFAULT_SINGLE_STREAM_BELOW_BYTES,
+    DEFAULT_TWO_CHUNKS_BELOW_BYTES, DOWNLOAD_MODE_PARALLEL, DOWNLOAD_MODE_SINGLE, MAX_DOWNLOAD_BUFFER_KB,
+    MIN_DOWNLOAD_BUFFER_KB,
+};
+use crate::error::AppError;
+use crate::logging::{self, DEFAULT_LOG_LEVEL};
+use crate::net::{DEFAULT_MAX_CONNECTIONS, DEFAULT_REQUEST_TIMEOUT_SECS, DEFAULT_USER_AGENT};
+use crate::db;
+use crate::paths::default_download_dir;
+use crate::queue::DEFAULT_MAX_CONCURRENT_DOWNLOADS;
+use crate::start::DEFAULT_SCRAPE_INTERVAL_HOURS;
 
 #[tauri::command]
-pub fn clear_download_dir() -> Result<(), String> {
-    let conn = Connection::open(db_path()).map_err(|e| format!("Failed to open DB: {}", e))?;
+pub fn clear_download_dir() -> Result<(), AppError> {
+    let conn = db::open().map_err(|e| AppError::db(format!("Failed to open DB: {}", e)))?;
     ensure_settings_table(&conn)?;
 
     conn.execute("DELETE FROM settings WHERE key = 'download_dir'", [])
-        .map_err(|e| format!("Failed to clear download_dir: {}", e))?;
+        .map_err(|e| AppError::db(format!("Failed to clear download_dir: {}", e)))?;
 
     Ok(())
 }
 
 
-fn ensure_settings_table(conn: &Connection) -> Result<(), String> {
+fn ensure_settings_table(conn: &Connection) -> Result<(), AppError> {
     conn.execute(
         "CREATE TABLE IF NOT EXISTS settings (key TEXT PRIMARY KEY, value TEXT NOT NULL)",
         [],
     )
-    .map_err(|e| format!("Failed creating settings table: {}", e))?;
+    .map_err(|e| AppError::db(format!("Failed creating settings table: {}", e)))?;
+    Ok(())
+}
+
+/// Reads a raw value from the settings table, `None` if it was never set.
+pub(crate) fn read_setting(key: &str) -> Result<Option<String>, AppError> {
+    let conn = db::open().map_err(|e| AppError::db(format!("Failed to open DB: {}", e)))?;
+    ensure_settings_table(&conn)?;
+
+    let value: Result<String, _> = conn.query_row(
+        "SELECT value FROM settings WHERE key = ?1",
+        [key],
+        |row| row.get(0),
+    );
+
+    match value {
+        Ok(v) => Ok(Some(v)),
+        Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+        Err(e) => Err(AppError::db(format!("Failed to read {}: {}", key, e))),
+    }
+}
+
+pub(crate) fn write_setting(key: &str, value: &str) -> Result<(), AppError> {
+    let conn = db::open().map_err(|e| AppError::db(format!("Failed to open DB: {}", e)))?;
+    ensure_settings_table(&conn)?;
+
+    conn.execute(
+        "INSERT INTO settings(key, value) VALUES(?1, ?2)
+         ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+        [key, value],
+    )
+    .map_err(|e| AppError::db(format!("Failed to save {}: {}", key, e)))?;
+
+    Ok(())
+}
+
+pub(crate) fn delete_setting(key: &str) -> Result<(), AppError> {
+    let conn = db::open().map_err(|e| AppError::db(format!("Failed to open DB: {}", e)))?;
+    ensure_settings_table(&conn)?;
+
+    conn.execute("DELETE FROM settings WHERE key = ?1", [key])
+        .map_err(|e| AppError::db(format!("Failed to clear {}: {}", key, e)))?;
+
     Ok(())
 }
 
+pub(crate) fn read_bool_setting(key: &str, default: bool) -> Result<bool, AppError> {
+    match read_setting(key)?.as_deref().map(str::trim) {
+        Some("1") | Some("true") => Ok(true),
+        Some("0") | Some("false") => Ok(false),
+        _ => Ok(default),
+    }
+}
+
+pub(crate) fn read_u64_setting(key: &str, default: u64) -> Result<u64, AppError> {
+    Ok(read_setting(key)?
+        .and_then(|v| v.trim().parse().ok())
+        .unwrap_or(default))
+}
+
+pub(crate) fn write_bool_setting(key: &str, value: bool) -> Result<(), AppError> {
+    write_setting(key, if value { "1" } else { "0" })
+}
+
 #[tauri::command]
-pub fn get_download_dir() -> Result<String, String> {
-    let conn = Connection::open(db_path()).map_err(|e| format!("Failed to open DB: {}", e))?;
+pub fn get_download_dir() -> Result<String, AppError> {
+    let conn = db::open().map_err(|e| AppError::db(format!("Failed to open DB: {}", e)))?;
     ensure_settings_table(&conn)?;
 
     let mut stmt = conn
         .prepare("SELECT value FROM settings WHERE key = 'download_dir'")
-        .map_err(|e| format!("Failed to prepare: {}", e))?;
+        .map_err(|e| AppError::db(format!("Failed to prepare: {}", e)))?;
 
     let value: Result<String, _> = stmt.query_row([], |row| row.get(0));
 
@@ -51,13 +117,47 @@ pub fn get_download_dir() -> Result<String, String> {
     }
 }
 
+/// Creates `dir` if needed and proves we can write there with a throwaway probe file.
+pub(crate) fn ensure_writable_dir(dir: &Path) -> Result<(), AppError> {
+    std::fs::create_dir_all(dir)
+        .map_err(|e| AppError::io(format!("Cannot create folder {:?}: {}", dir, e)))?;
+
+    let probe = dir.join(".rusty-roms-write-test");
+    std::fs::write(&probe, b"ok")
+        .map_err(|e| AppError::io(format!("Folder {:?} is not writable: {}", dir, e)))?;
+    let _ = std::fs::remove_file(&probe);
+
+    Ok(())
+}
+
+#[derive(serde::Serialize)]
+pub struct DownloadDirStatus {
+    pub path: String,
+    pub writable: bool,
+    pub error: Option<String>,
+}
+
+/// The effective download dir plus whether downloads can actually be written to it.
+#[tauri::command]
+pub fn get_download_dir_status() -> Result<DownloadDirStatus, AppError> {
+    let path = get_download_dir()?;
+    let error = ensure_writable_dir(Path::new(&path)).err().map(|e| e.to_string());
+
+    Ok(DownloadDirStatus {
+        path,
+        writable: error.is_none(),
+        error,
+    })
+}
+
 #[tauri::command]
-pub fn set_download_dir(path: String) -> Result<(), String> {
+pub fn set_download_dir(path: String) -> Result<(), AppError> {
     if path.trim().is_empty() {
-        return Err("Path cannot be empty".into());
+        return Err(AppError::invalid_input("Path cannot be empty"));
     }
+    ensure_writable_dir(Path::new(&path))?;
 
-    let conn = Connection::open(db_path()).map_err(|e| format!("Failed to open DB: {}", e))?;
+    let conn = db::open().map_err(|e| AppError::db(format!("Failed to open DB: {}", e)))?;
     ensure_settings_table(&conn)?;
 
     conn.execute(
@@ -65,13 +165,75 @@ pub fn set_download_dir(path: String) -> Result<(), String> {
          ON CONFLICT(key) DO UPDATE SET value = excluded.value",
         [&path],
     )
-    .map_err(|e| format!("Failed to save download_dir: {}", e))?;
+    .map_err(|e| AppError::db(format!("Failed to save download_dir: {}", e)))?;
 
+    info!("Download folder set to {}", path);
     Ok(())
 }
 
+/// Saves a folder setting after checking it's writable, or clears it with `None`/empty.
+fn set_dir_setting(key: &str, path: Option<String>) -> Result<(), AppError> {
+    match path.map(|p| p.trim().to_string()).filter(|p| !p.is_empty()) {
+        Some(path) => {
+            ensure_writable_dir(Path::new(&path))?;
+            write_setting(key, &path)?;
+            info!("{} set to {}", key, path);
+            Ok(())
+        }
+        None => delete_setting(key),
+    }
+}
+
+#[tauri::command]
+pub fn get_staging_dir() -> Result<Option<String>, AppError> {
+    read_setting("staging_dir")
+}
+
+/// Where downloads are fetched and extracted before moving into the library.
+/// Unset, they're written straight into the library.
+#[tauri::command]
+pub fn set_staging_dir(path: Option<String>) -> Result<(), AppError> {
+    set_dir_setting("staging_dir", path)
+}
+
+#[tauri::command]
+pub fn get_library_dir() -> Result<Option<String>, AppError> {
+    read_setting("library_dir")
+}
+
+/// Final home for completed downloads; takes precedence over `download_dir`.
+/// Per-console folders still win for their console.
+#[tauri::command]
+pub fn set_library_dir(path: Option<String>) -> Result<(), AppError> {
+    set_dir_setting("library_dir", path)
+}
+
+#[tauri::command]
+pub fn get_zip_dir() -> Result<Option<String>, AppError> {
+    read_setting("zip_dir")
+}
+
+/// Where archives are downloaded and kept, e.g. a fast SSD. Unset, they go to the
+/// download folder. Per-console folders still win for their console.
+#[tauri::command]
+pub fn set_zip_dir(path: Option<String>) -> Result<(), AppError> {
+    set_dir_setting("zip_dir", path)
+}
+
 #[tauri::command]
-pub async fn pick_download_dir(window: Window) -> Result<Option<String>, String> {
+pub fn get_extract_dir() -> Result<Option<String>, AppError> {
+    read_setting("extract_dir")
+}
+
+/// Where archives are extracted to, e.g. a big HDD. Unset, they're extracted into the
+/// download folder. Per-console folders still win for their console.
+#[tauri::command]
+pub fn set_extract_dir(path: Option<String>) -> Result<(), AppError> {
+    set_dir_setting("extract_dir", path)
+}
+
+#[tauri::command]
+pub async fn pick_download_dir(window: Window) -> Result<Option<String>, AppError> {
     // tauri-plugin-dialog 2.6.0 uses callbacks, so we bridge it to async.
     let (tx, rx) = tokio::sync::oneshot::channel::<Option<FilePath>>();
 
@@ -98,8 +260,417 @@ pub async fn pick_download_dir(window: Window) -> Result<Option<String>, String>
 
     let picked: Option<FilePath> = rx
         .await
-        .map_err(|_| "Folder picker was cancelled or closed".to_string())?;
+        .map_err(|_| AppError::invalid_input("Folder picker was cancelled or closed"))?;
 
     Ok(picked.map(|p| p.to_string()))
 }
 
+#[tauri::command]
+pub fn get_delete_zip_after_extract() -> Result<bool, AppError> {
+    read_bool_setting("delete_zip_after_extract", true)
+}
+
+#[tauri::command]
+pub fn set_delete_zip_after_extract(value: bool) -> Result<(), AppError> {
+    write_bool_setting("delete_zip_after_extract", value)
+}
+
+#[tauri::command]
+pub fn get_verify_before_delete() -> Result<bool, AppError> {
+    read_bool_setting("verify_before_delete", false)
+}
+
+/// Checks extracted files against the archive's CRC-32s before the archive is deleted.
+/// On a mismatch the download fails: the archive is kept, nothing is marked downloaded
+/// and `download-corrupt` is emitted.
+#[tauri::command]
+pub fn set_verify_before_delete(value: bool) -> Result<(), AppError> {
+    write_bool_setting("verify_before_delete", value)
+}
+
+#[tauri::command]
+pub fn get_auto_extract() -> Result<bool, AppError> {
+    read_bool_setting("auto_extract", true)
+}
+
+#[tauri::command]
+pub fn set_auto_extract(value: bool) -> Result<(), AppError> {
+    write_bool_setting("auto_extract", value)
+}
+
+#[tauri::command]
+pub fn get_organize_by_console() -> Result<bool, AppError> {
+    read_bool_setting("organize_by_console", true)
+}
+
+#[tauri::command]
+pub fn set_organize_by_console(value: bool) -> Result<(), AppError> {
+    write_bool_setting("organize_by_console", value)
+}
+
+#[tauri::command]
+pub fn get_compact_after_scrape() -> Result<bool, AppError> {
+    read_bool_setting("compact_after_scrape", false)
+}
+
+#[tauri::command]
+pub fn set_compact_after_scrape(value: bool) -> Result<(), AppError> {
+    write_bool_setting("compact_after_scrape", value)
+}
+
+#[tauri::command]
+pub fn get_scrape_on_startup() -> Result<bool, AppError> {
+    read_bool_setting("scrape_on_startup", true)
+}
+
+/// Off: the launch-time run_startup_tasks never scrapes; only a manual refresh does.
+#[tauri::command]
+pub fn set_scrape_on_startup(value: bool) -> Result<(), AppError> {
+    write_bool_setting("scrape_on_startup", value)
+}
+
+#[tauri::command]
+pub fn get_cleanup_partials_on_start() -> Result<bool, AppError> {
+    read_bool_setting("cleanup_partials_on_start", false)
+}
+
+/// On: startup removes archives and partial files left by downloads that never finished.
+#[tauri::command]
+pub fn set_cleanup_partials_on_start(value: bool) -> Result<(), AppError> {
+    write_bool_setting("cleanup_partials_on_start", value)
+}
+
+#[tauri::command]
+pub fn get_scrape_interval_hours() -> Result<u64, AppError> {
+    read_u64_setting("scrape_interval_hours", DEFAULT_SCRAPE_INTERVAL_HOURS)
+}
+
+/// How old the catalog has to be before launch re-scrapes it; 0 scrapes every launch.
+#[tauri::command]
+pub fn set_scrape_interval_hours(hours: u64) -> Result<(), AppError> {
+    write_setting("scrape_interval_hours", &hours.to_string())
+}
+
+#[tauri::command]
+pub fn get_keep_partial_extractions() -> Result<bool, AppError> {
+    read_bool_setting("keep_partial_extractions", false)
+}
+
+/// On: archive entries that fail to extract are skipped and reported (`extract-partial`)
+/// and the rest are kept. Off: any failed entry fails the whole download.
+#[tauri::command]
+pub fn set_keep_partial_extractions(value: bool) -> Result<(), AppError> {
+    write_bool_setting("keep_partial_extractions", value)
+}
+
+#[tauri::command]
+pub fn get_proxy() -> Result<Option<String>, AppError> {
+    read_setting("proxy_url")
+}
+
+/// Saves an http(s)/socks5 proxy used for all requests, or clears it with `None`/empty.
+#[tauri::command]
+pub fn set_proxy(url: Option<String>) -> Result<(), AppError> {
+    let url = url.map(|u| u.trim().to_string()).filter(|u| !u.is_empty());
+
+    match url {
+        Some(url) => {
+            let parsed = reqwest::Url::parse(&url)
+                .map_err(|e| AppError::invalid_input(format!("Invalid proxy URL: {}", e)))?;
+            if !matches!(parsed.scheme(), "http" | "https" | "socks5" | "socks5h") {
+                return Err(AppError::invalid_input(format!("Unsupported proxy scheme: {}", parsed.scheme())));
+            }
+            reqwest::Proxy::all(&url).map_err(|e| AppError::invalid_input(format!("Invalid proxy URL: {}", e)))?;
+            write_setting("proxy_url", &url)
+        }
+        None => delete_setting("proxy_url"),
+    }
+}
+
+#[tauri::command]
+pub fn get_mirror() -> Result<Option<String>, AppError> {
+    read_setting("mirror_base")
+}
+
+/// Saves the mirror (scheme and host) that Myrient console and game links are rewritten
+/// to, or clears it with `None`/empty to use the scraped hosts as-is.
+#[tauri::command]
+pub fn set_mirror(base_url: Option<String>) -> Result<(), AppError> {
+    let base_url = base_url.map(|u| u.trim().to_string()).filter(|u| !u.is_empty());
+
+    match base_url {
+        Some(url) => {
+            let parsed = reqwest::Url::parse(&url)
+                .map_err(|e| AppError::invalid_input(format!("Invalid mirror URL: {}", e)))?;
+            if !matches!(parsed.scheme(), "http" | "https") {
+                return Err(AppError::invalid_input("Mirror URL must start with http:// or https://"));
+            }
+            let Some(host) = parsed.host_str() else {
+                return Err(AppError::invalid_input("Mirror URL has no host"));
+            };
+            // Only the host is swapped in, so store just that part
+            let base = match parsed.port() {
+                Some(port) => format!("{}://{}:{}/", parsed.scheme(), host, port),
+                None => format!("{}://{}/", parsed.scheme(), host),
+            };
+            write_setting("mirror_base", &base)
+        }
+        None => delete_setting("mirror_base"),
+    }
+}
+
+#[tauri::command]
+pub fn get_request_timeout() -> Result<u64, AppError> {
+    read_u64_setting("request_timeout_secs", DEFAULT_REQUEST_TIMEOUT_SECS)
+}
+
+#[tauri::command]
+pub fn set_request_timeout(secs: u64) -> Result<(), AppError> {
+    if secs == 0 {
+        return Err(AppError::invalid_input("Timeout must be at least 1 second"));
+    }
+    write_setting("request_timeout_secs", &secs.to_string())
+}
+
+#[tauri::command]
+pub fn get_user_agent() -> Result<String, AppError> {
+    Ok(read_setting("user_agent")?
+        .filter(|ua| !ua.trim().is_empty())
+        .unwrap_or_else(|| DEFAULT_USER_AGENT.to_string()))
+}
+
+/// Saves a custom User-Agent; `None`/empty goes back to the default.
+#[tauri::command]
+pub fn set_user_agent(user_agent: Option<String>) -> Result<(), AppError> {
+    match user_agent.map(|ua| ua.trim().to_string()).filter(|ua| !ua.is_empty()) {
+        Some(ua) => {
+            reqwest::header::HeaderValue::from_str(&ua)
+                .map_err(|e| AppError::invalid_input(format!("Invalid User-Agent: {}", e)))?;
+            write_setting("user_agent", &ua)
+        }
+        None => delete_setting("user_agent"),
+    }
+}
+
+#[tauri::command]
+pub fn get_refresh_stale_links() -> Result<bool, AppError> {
+    read_bool_setting("refresh_stale_links", false)
+}
+
+#[tauri::command]
+pub fn set_refresh_stale_links(value: bool) -> Result<(), AppError> {
+    write_bool_setting("refresh_stale_links", value)
+}
+
+#[tauri::command]
+pub fn get_max_concurrent_downloads() -> Result<u64, AppError> {
+    read_u64_setting("max_concurrent_downloads", DEFAULT_MAX_CONCURRENT_DOWNLOADS)
+}
+
+#[tauri::command]
+pub fn set_max_concurrent_downloads(count: u64) -> Result<(), AppError> {
+    if count == 0 {
+        return Err(AppError::invalid_input("At least one download must be allowed"));
+    }
+    write_setting("max_concurrent_downloads", &count.to_string())
+}
+
+#[tauri::command]
+pub fn get_max_connections() -> Result<u64, AppError> {
+    read_u64_setting("max_connections", DEFAULT_MAX_CONNECTIONS)
+}
+
+// Beyond this a mirror is more likely to throttle or ban than to go faster
+const MAX_CONNECTIONS_LIMIT: u64 = 64;
+
+/// Cap on HTTP requests open at once across downloads (every chunk counts), HEAD probes
+/// and scraping. Defaults to `DEFAULT_MAX_CONNECTIONS` (8).
+#[tauri::command]
+pub fn set_max_connections(count: u64) -> Result<(), AppError> {
+    if !(1..=MAX_CONNECTIONS_LIMIT).contains(&count) {
+        return Err(AppError::invalid_input(format!(
+            "Connection limit must be between 1 and {}",
+            MAX_CONNECTIONS_LIMIT
+        )));
+    }
+    write_setting("max_connections", &count.to_string())
+}
+
+#[derive(serde::Serialize)]
+pub struct ConsoleDir {
+    pub console: String,
+    pub path: String,
+}
+
+#[tauri::command]
+pub fn get_console_dirs() -> Result<Vec<ConsoleDir>, AppError> {
+    let conn = db::open().map_err(|e| AppError::db(format!("Failed to open DB: {}", e)))?;
+
+    let mut stmt = conn
+        .prepare("SELECT console, path FROM console_dirs ORDER BY console")
+        .map_err(|e| AppError::db(format!("Failed to prepare: {}", e)))?;
+
+    let dirs = stmt
+        .query_map([], |row| {
+            Ok(ConsoleDir {
+                console: row.get(0)?,
+                path: row.get(1)?,
+            })
+        })
+        .map_err(|e| AppError::db(format!("Query failed: {}", e)))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| AppError::db(format!("Row error: {}", e)))?;
+
+    Ok(dirs)
+}
+
+/// Points one console's downloads at `path`; an empty path removes the override.
+#[tauri::command]
+pub fn set_console_dir(console: String, path: String) -> Result<(), AppError> {
+    let conn = db::open().map_err(|e| AppError::db(format!("Failed to open DB: {}", e)))?;
+
+    if path.trim().is_empty() {
+        conn.execute("DELETE FROM console_dirs WHERE console = ?1", [&console])
+            .map_err(|e| AppError::db(format!("Failed to clear console dir: {}", e)))?;
+        return Ok(());
+    }
+    ensure_writable_dir(Path::new(&path))?;
+
+    conn.execute(
+        "INSERT INTO console_dirs(console, path) VALUES(?1, ?2)
+         ON CONFLICT(console) DO UPDATE SET path = excluded.path",
+        [&console, &path],
+    )
+    .map_err(|e| AppError::db(format!("Failed to save console dir: {}", e)))?;
+
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_scrape_retries() -> Result<u64, AppError> {
+    read_u64_setting("scrape_retries", DEFAULT_SCRAPE_RETRIES)
+}
+
+#[tauri::command]
+pub fn set_scrape_retries(retries: u64) -> Result<(), AppError> {
+    write_setting("scrape_retries", &retries.to_string())
+}
+
+#[tauri::command]
+pub fn get_scrape_retry_backoff_ms() -> Result<u64, AppError> {
+    read_u64_setting("scrape_retry_backoff_ms", DEFAULT_SCRAPE_RETRY_BACKOFF_MS)
+}
+
+#[tauri::command]
+pub fn set_scrape_retry_backoff_ms(ms: u64) -> Result<(), AppError> {
+    write_setting("scrape_retry_backoff_ms", &ms.to_string())
+}
+
+#[tauri::command]
+pub fn get_scrape_delay_ms() -> Result<u64, AppError> {
+    read_u64_setting("scrape_delay_ms", DEFAULT_SCRAPE_DELAY_MS)
+}
+
+#[tauri::command]
+pub fn set_scrape_delay_ms(ms: u64) -> Result<(), AppError> {
+    write_setting("scrape_delay_ms", &ms.to_string())
+}
+
+#[tauri::command]
+pub fn get_download_buffer_kb() -> Result<u64, AppError> {
+    read_u64_setting("download_buffer_kb", DEFAULT_DOWNLOAD_BUFFER_KB)
+}
+
+/// Read buffer size for downloads; bigger buffers mean fewer syscalls on fast links.
+#[tauri::command]
+pub fn set_download_buffer_kb(kb: u64) -> Result<(), AppError> {
+    if !(MIN_DOWNLOAD_BUFFER_KB..=MAX_DOWNLOAD_BUFFER_KB).contains(&kb) {
+        return Err(AppError::invalid_input(format!(
+            "Buffer size must be between {} and {} KiB",
+            MIN_DOWNLOAD_BUFFER_KB, MAX_DOWNLOAD_BUFFER_KB
+        )));
+    }
+    write_setting("download_buffer_kb", &kb.to_string())
+}
+
+// Upper bound on max_download_chunks; more connections than this just annoys the mirror
+const MAX_DOWNLOAD_CHUNKS_LIMIT: u64 = 16;
+
+/// How many ranged connections a parallel download uses, by file size.
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct ChunkSettings {
+    pub single_stream_below_bytes: u64,
+    pub two_chunks_below_bytes: u64,
+    pub max_download_chunks: u64,
+}
+
+#[tauri::command]
+pub fn get_chunk_settings() -> Result<ChunkSettings, AppError> {
+    Ok(ChunkSettings {
+        single_stream_below_bytes: read_u64_setting("single_stream_below_bytes", DEFAULT_SINGLE_STREAM_BELOW_BYTES)?,
+        two_chunks_below_bytes: read_u64_setting("two_chunks_below_bytes", DEFAULT_TWO_CHUNKS_BELOW_BYTES)?,
+        max_download_chunks: read_u64_setting("max_download_chunks", DEFAULT_MAX_DOWNLOAD_CHUNKS)?,
+    })
+}
+
+/// Files under `single_stream_below_bytes` use one connection, under
+/// `two_chunks_below_bytes` two, anything bigger `max_download_chunks`.
+#[tauri::command]
+pub fn set_chunk_settings(settings: ChunkSettings) -> Result<(), AppError> {
+    if settings.single_stream_below_bytes > settings.two_chunks_below_bytes {
+        return Err(AppError::invalid_input("The single-stream threshold can't be above the two-chunk one"));
+    }
+    if !(1..=MAX_DOWNLOAD_CHUNKS_LIMIT).contains(&settings.max_download_chunks) {
+        return Err(AppError::invalid_input(format!(
+            "Max chunks must be between 1 and {}",
+            MAX_DOWNLOAD_CHUNKS_LIMIT
+        )));
+    }
+
+    write_setting("single_stream_below_bytes", &settings.single_stream_below_bytes.to_string())?;
+    write_setting("two_chunks_below_bytes", &settings.two_chunks_below_bytes.to_string())?;
+    write_setting("max_download_chunks", &settings.max_download_chunks.to_string())
+}
+
+#[tauri::command]
+pub fn get_download_mode() -> Result<String, AppError> {
+    Ok(read_setting("download_mode")?.unwrap_or_else(|| DOWNLOAD_MODE_PARALLEL.to_string()))
+}
+
+/// `parallel` splits a download over ranged connections (see `ChunkSettings`); `single` always uses one.
+#[tauri::command]
+pub fn set_download_mode(mode: String) -> Result<(), AppError> {
+    match mode.as_str() {
+        DOWNLOAD_MODE_PARALLEL | DOWNLOAD_MODE_SINGLE => write_setting("download_mode", &mode),
+        other => Err(AppError::invalid_input(format!(
+            "Unknown download mode {:?} (expected {} or {})",
+            other, DOWNLOAD_MODE_PARALLEL, DOWNLOAD_MODE_SINGLE
+        ))),
+    }
+}
+
+#[tauri::command]
+pub fn get_log_level() -> Result<String, AppError> {
+    Ok(read_setting("log_level")?.unwrap_or_else(|| DEFAULT_LOG_LEVEL.to_string()))
+}
+
+/// One of error, warn, info, debug, trace; applies immediately.
+#[tauri::command]
+pub fn set_log_level(level: String) -> Result<(), AppError> {
+    let filter = logging::parse_level(&level)?;
+    write_setting("log_level", &filter.to_string().to_ascii_lowercase())?;
+    logging::set_level(filter)?;
+    info!("Log level set to {}", filter);
+    Ok(())
+}
+
+/// 0 means the library may grow without limit.
+#[tauri::command]
+pub fn get_max_library_size_bytes() -> Result<u64, AppError> {
+    read_u64_setting("max_library_size_bytes", 0)
+}
+
+#[tauri::command]
+pub fn set_max_library_size_bytes(bytes: u64) -> Result<(), AppError> {
+    write_setting("max_library_size_bytes", &bytes.to_string())
+}
@@ -0,0 +1,322 @@
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use tauri::{Emitter, Window};
+use tokio::task;
+
+use crate::data;
+use crate::db;
+use crate::download;
+use crate::error::AppError;
+use crate::net;
+
+#[derive(Serialize, Clone, Debug)]
+pub struct ConsoleSource {
+    pub name: String,
+    pub url: String,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct ConsoleScrapeStatus {
+    pub console: String,
+    pub last_scraped: Option<i64>, // unix seconds; None if never scraped since the upgrade
+    pub game_count: u64,           // games still listed (available)
+}
+
+#[derive(Serialize, Clone, Debug)]
+struct ConsoleRefreshPayload {
+    console: String,
+    percent: u8,
+    message: String,
+}
+
+fn open_db() -> Result<Connection, AppError> {
+    // make sure consoles/games exist even before the first scrape
+    data::setup().map_err(|e| AppError::db(format!("Failed creating tables: {}", e)))?;
+    db::open().map_err(|e| AppError::db(format!("Failed to open DB: {}", e)))
+}
+
+#[tauri::command]
+pub fn list_console_sources() -> Result<Vec<ConsoleSource>, AppError> {
+    let conn = open_db()?;
+
+    let mut stmt = conn
+        .prepare("SELECT console, url FROM consoles ORDER BY id")
+        .map_err(|e| AppError::db(format!("Failed to prepare query: {}", e)))?;
+
+    let sources = stmt
+        .query_map([], |row| {
+            Ok(ConsoleSource {
+                name: row.get(0)?,
+                url: row.get(1)?,
+            })
+        })
+        .map_err(|e| AppError::db(format!("Query execution failed: {}", e)))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| AppError::db(format!("Row error: {}", e)))?;
+
+    Ok(sources)
+}
+
+/// When each console was last scraped and how many games it lists, oldest scrape first,
+/// so a refresh can pick just the stale ones.
+#[tauri::command]
+pub fn console_scrape_status() -> Result<Vec<ConsoleScrapeStatus>, AppError> {
+    let conn = open_db()?;
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT c.console, c.last_scraped,
+                    (SELECT COUNT(*) FROM games g WHERE g.console = c.console AND g.available = 1)
+             FROM consoles c
+             ORDER BY c.last_scraped IS NOT NULL, c.last_scraped, c.id",
+        )
+        .map_err(|e| AppError::db(format!("Failed to prepare query: {}", e)))?;
+
+    let statuses = stmt
+        .query_map([], |row| {
+            Ok(ConsoleScrapeStatus {
+                console: row.get(0)?,
+                last_scraped: row.get(1)?,
+                game_count: row.get::<_, i64>(2)? as u64,
+            })
+        })
+        .map_err(|e| AppError::db(format!("Query execution failed: {}", e)))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| AppError::db(format!("Row error: {}", e)))?;
+
+    Ok(statuses)
+}
+
+#[tauri::command]
+pub fn add_console(name: String, url: String) -> Result<(), AppError> {
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err(AppError::invalid_input("Console name cannot be empty"));
+    }
+
+    let parsed = reqwest::Url::parse(url.trim()).map_err(|e| AppError::invalid_input(format!("Invalid URL: {}", e)))?;
+    if parsed.scheme() != "http" && parsed.scheme() != "https" {
+        return Err(AppError::invalid_input("URL must start with http:// or https://"));
+    }
+
+    // game links are built as `url + href`, so the listing URL has to end in a slash
+    let mut url = parsed.to_string();
+    if !url.ends_with('/') {
+        url.push('/');
+    }
+
+    let conn = open_db()?;
+
+    let exists: i64 = conn
+        .query_row(
+            "SELECT COUNT(*) FROM consoles WHERE console = ?1",
+            [&name],
+            |row| row.get(0),
+        )
+        .map_err(|e| AppError::db(format!("Failed to check console: {}", e)))?;
+    if exists > 0 {
+        return Err(AppError::invalid_input(format!("Console already exists: {}", name)));
+    }
+
+    conn.execute(
+        "INSERT INTO consoles (console, url) VALUES (?1, ?2)",
+        params![name, url],
+    )
+    .map_err(|e| AppError::db(format!("Failed to add console: {}", e)))?;
+
+    Ok(())
+}
+
+/// Removes the source and the games scraped from it.
+#[tauri::command]
+pub fn remove_console(name: String) -> Result<(), AppError> {
+    let conn = open_db()?;
+
+    let removed = conn
+        .execute("DELETE FROM consoles WHERE console = ?1", [&name])
+        .map_err(|e| AppError::db(format!("Failed to remove console: {}", e)))?;
+    if removed == 0 {
+        return Err(AppError::not_found(format!("No such console: {}", name)));
+    }
+
+    conn.execute(
+        "DELETE FROM game_parts WHERE game_id IN (SELECT id FROM games WHERE console = ?1)",
+        [&name],
+    )
+    .map_err(|e| AppError::db(format!("Failed to remove split archive parts for {}: {}", name, e)))?;
+    conn.execute("DELETE FROM games WHERE console = ?1", [&name])
+        .map_err(|e| AppError::db(format!("Failed to remove games for {}: {}", name, e)))?;
+    download::forget_console_size(&name);
+
+    Ok(())
+}
+
+/// Extra request headers (e.g. an auth token or referer) sent to this source when
+/// scraping it and downloading from it. `None` or an empty map clears them.
+#[tauri::command]
+pub fn set_console_headers(name: String, headers: Option<BTreeMap<String, String>>) -> Result<(), AppError> {
+    let json = match headers.filter(|h| !h.is_empty()) {
+        Some(headers) => {
+            let json = serde_json::to_string(&headers)
+                .map_err(|e| AppError::internal(format!("Failed to encode headers: {}", e)))?;
+            // Reject anything reqwest wouldn't send, rather than failing at scrape time
+            net::parse_headers(Some(&json))?;
+            Some(json)
+        }
+        None => None,
+    };
+
+    let conn = open_db()?;
+    let updated = conn
+        .execute(
+            "UPDATE consoles SET headers = ?1 WHERE console = ?2",
+            params![json, name],
+        )
+        .map_err(|e| AppError::db(format!("Failed to save headers: {}", e)))?;
+    if updated == 0 {
+        return Err(AppError::not_found(format!("No such console: {}", name)));
+    }
+
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_console_headers(name: String) -> Result<BTreeMap<String, String>, AppError> {
+    let conn = open_db()?;
+    let json: Option<String> = conn
+        .query_row(
+            "SELECT headers FROM consoles WHERE console = ?1",
+            [&name],
+            |row| row.get(0),
+        )
+        .map_err(|e| AppError::not_found(format!("No such console {}: {}", name, e)))?;
+
+    match json.filter(|j| !j.trim().is_empty()) {
+        Some(json) => serde_json::from_str(&json)
+            .map_err(|e| AppError::db(format!("Stored headers for {} are invalid: {}", name, e))),
+        None => Ok(BTreeMap::new()),
+    }
+}
+
+fn emit_refresh(window: &Window, console: &str, percent: u8, message: String) {
+    let _ = window.emit(
+        "console-refresh-progress",
+        ConsoleRefreshPayload {
+            console: console.to_string(),
+            percent,
+            message,
+        },
+    );
+}
+
+/// Re-scrapes one console in place (incremental), keeping downloaded flags.
+/// Returns how many games it holds now, after the scrape filters.
+#[tauri::command]
+pub async fn refresh_console(name: String, window: Window) -> Result<usize, AppError> {
+    let refresh_task = task::spawn_blocking(move || -> Result<usize, AppError> {
+        // Make sure the tables exist before scraping into them
+        open_db()?;
+
+        emit_refresh(&window, &name, 0, format!("Refreshing {}…", name));
+        match data::scrape_console(&name) {
+            Ok(count) => {
+                emit_refresh(&window, &name, 100, format!("Refreshed {}: {} games", name, count));
+                Ok(count)
+            }
+            Err(e) => {
+                emit_refresh(&window, &name, 100, format!("Failed to refresh {}: {}", name, e));
+                Err(e)
+            }
+        }
+    });
+
+    refresh_task.await.map_err(|e| AppError::internal(e.to_string()))?
+}
+
+/// Fetches and parses a console's listing live, without saving anything, so a
+/// just-added source can be browsed before it's scraped.
+#[tauri::command]
+pub async fn browse_console(name: String) -> Result<Vec<data::Game>, AppError> {
+    task::spawn_blocking(move || data::browse_console(&name))
+        .await
+        .map_err(|e| AppError::internal(e.to_string()))?
+}
+
+/// Re-reads just this game's console listing and updates its link, for a single
+/// download that started 404ing. Returns the new link.
+#[tauri::command]
+pub async fn refresh_link(id: u32) -> Result<String, AppError> {
+    task::spawn_blocking(move || data::refresh_game_link(id))
+        .await
+        .map_err(|e| AppError::internal(e.to_string()))?
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct ScrapeFilter {
+    pub id: i64,
+    pub kind: String, // "include" or "exclude"
+    pub pattern: String,
+}
+
+#[tauri::command]
+pub fn list_scrape_filters() -> Result<Vec<ScrapeFilter>, AppError> {
+    let conn = open_db()?;
+
+    let mut stmt = conn
+        .prepare("SELECT id, kind, pattern FROM scrape_filters ORDER BY kind, id")
+        .map_err(|e| AppError::db(format!("Failed to prepare query: {}", e)))?;
+
+    let filters = stmt
+        .query_map([], |row| {
+            Ok(ScrapeFilter {
+                id: row.get(0)?,
+                kind: row.get(1)?,
+                pattern: row.get(2)?,
+            })
+        })
+        .map_err(|e| AppError::db(format!("Query execution failed: {}", e)))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| AppError::db(format!("Row error: {}", e)))?;
+
+    Ok(filters)
+}
+
+/// Adds a regex matched against listing names on the next scrape. `include` patterns
+/// limit the catalog to matching games; `exclude` patterns drop matching ones.
+#[tauri::command]
+pub fn add_scrape_filter(kind: String, pattern: String) -> Result<(), AppError> {
+    if kind != "include" && kind != "exclude" {
+        return Err(AppError::invalid_input(format!("Filter kind must be include or exclude, not {}", kind)));
+    }
+    let pattern = pattern.trim().to_string();
+    if pattern.is_empty() {
+        return Err(AppError::invalid_input("Filter pattern cannot be empty"));
+    }
+    regex::Regex::new(&pattern).map_err(|e| AppError::invalid_input(format!("Invalid pattern: {}", e)))?;
+
+    let conn = open_db()?;
+    conn.execute(
+        "INSERT OR IGNORE INTO scrape_filters (kind, pattern) VALUES (?1, ?2)",
+        params![kind, pattern],
+    )
+    .map_err(|e| AppError::db(format!("Failed to add filter: {}", e)))?;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn remove_scrape_filter(id: i64) -> Result<(), AppError> {
+    let conn = open_db()?;
+    conn.execute("DELETE FROM scrape_filters WHERE id = ?1", [id])
+        .map_err(|e| AppError::db(format!("Failed to remove filter: {}", e)))?;
+    Ok(())
+}
+
+/// Removes every filter, defaults included, so the next scrape keeps the full listing.
+#[tauri::command]
+pub fn clear_scrape_filters() -> Result<(), AppError> {
+    let conn = open_db()?;
+    conn.execute("DELETE FROM scrape_filters", [])
+        .map_err(|e| AppError::db(format!("Failed to clear filters: {}", e)))?;
+    Ok(())
+}
@@ -1,53 +1,188 @@
 use serde::Serialize;
 use tauri::{Emitter, Window};
 use tokio::task;
+use tracing::warn;
 
 use crate::data;
+use crate::error::AppError;
+use crate::library;
+use crate::maintenance;
+use crate::history::now_unix;
+use crate::settings::{read_bool_setting, read_setting, read_u64_setting, write_setting};
 
 #[derive(Serialize, Clone, Debug)]
 struct StartupProgressPayload {
     percent: u8,
     message: String,
+    console: Option<String>,      // console that just finished scraping
+    console_games: Option<usize>, // games found for it
+    total_games: usize,           // games scraped so far
 }
 
-fn emit_progress(window: &Window, percent: u8, message: impl Into<String>) -> Result<(), String> {
+fn emit_payload(window: &Window, payload: StartupProgressPayload) -> Result<(), AppError> {
     window
-        .emit(
-            "startup-progress",
-            StartupProgressPayload {
-                percent,
-                message: message.into(),
-            },
-        )
-        .map_err(|e| format!("Emit failed: {}", e))
+        .emit("startup-progress", payload)
+        .map_err(|e| AppError::internal(format!("Emit failed: {}", e)))
 }
 
+fn emit_progress(window: &Window, percent: u8, message: impl Into<String>) -> Result<(), AppError> {
+    emit_payload(
+        window,
+        StartupProgressPayload {
+            percent,
+            message: message.into(),
+            console: None,
+            console_games: None,
+            total_games: 0,
+        },
+    )
+}
+
+#[derive(Serialize, Clone, Debug)]
+struct StartupCancelledPayload {
+    scraped: usize,     // consoles saved before the cancel
+    total_games: usize,
+}
+
+/// Stops `run_startup_tasks` after the consoles it already fetched; those stay saved.
 #[tauri::command]
-pub async fn run_startup_tasks(window: Window) -> Result<(), String> {
-    let task = task::spawn_blocking(move || -> Result<(), String> {
+pub fn cancel_startup() {
+    data::cancel_scrape();
+}
+
+pub const DEFAULT_SCRAPE_INTERVAL_HOURS: u64 = 24;
+
+/// Unix time the last scrape finished (cancelled ones don't count), if there was one.
+#[tauri::command]
+pub fn last_scrape_time() -> Result<Option<i64>, AppError> {
+    Ok(read_setting("last_scrape_at")?.and_then(|v| v.parse().ok()))
+}
+
+/// Whether a launch-time scrape should run: `scrape_on_startup` is on and the last
+/// scrape is at least `scrape_interval_hours` old (0 = every launch).
+fn scrape_due() -> Result<bool, AppError> {
+    if !read_bool_setting("scrape_on_startup", true)? {
+        return Ok(false);
+    }
+    let interval_secs = read_u64_setting("scrape_interval_hours", DEFAULT_SCRAPE_INTERVAL_HOURS)? as i64 * 3600;
+    Ok(match last_scrape_time()? {
+        Some(last) => now_unix() - last >= interval_secs,
+        None => true,
+    })
+}
+
+/// Removes leftovers of crashed downloads and reports the space freed (`partials-cleaned`).
+/// A failure here is logged and doesn't hold up the rest of startup.
+fn clean_up_partials(window: &Window) {
+    match library::cleanup_partials() {
+        Ok(report) => {
+            if !report.removed.is_empty() {
+                let _ = emit_progress(
+                    window,
+                    0,
+                    format!(
+                        "Removed {} partial downloads ({} MiB freed)",
+                        report.removed.len(),
+                        report.reclaimed_bytes / (1024 * 1024)
+                    ),
+                );
+            }
+            let _ = window.emit("partials-cleaned", report);
+        }
+        Err(e) => warn!("Skipped partial download cleanup: {}", e),
+    }
+}
+
+/// By default (or with `incremental`) the existing games and their downloaded flags are kept and
+/// refreshed in place; `incremental: false` rebuilds the games table from scratch. Console
+/// sources, settings, favorites and download history survive both.
+/// `only_if_due` is for the automatic run at launch: it does nothing (and returns a `skipped`
+/// summary) unless `scrape_due` says so. Left unset, e.g. for a manual refresh, it always runs.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn run_startup_tasks(
+    window: Window,
+    incremental: Option<bool>,
+    only_if_due: Option<bool>,
+) -> Result<data::ScrapeSummary, AppError> {
+    let incremental = incremental.unwrap_or(true);
+
+    let task = task::spawn_blocking(move || -> Result<data::ScrapeSummary, AppError> {
+        if read_bool_setting("cleanup_partials_on_start", false)? {
+            clean_up_partials(&window);
+        }
+
+        if only_if_due.unwrap_or(false) && !scrape_due()? {
+            emit_progress(&window, 100, "Catalog is up to date")?;
+            return Ok(data::ScrapeSummary {
+                skipped: true,
+                ..Default::default()
+            });
+        }
+
+        // Held until the scrape is done, so a restore can't swap the DB under it
+        let _scraping = maintenance::begin_scrape()?;
+        data::reset_scrape_cancel();
         emit_progress(&window, 0, "Starting…")?;
 
-        emit_progress(&window, 5, "Removing old DB…")?;
-        data::remove_old_db().map_err(|e| format!("remove_old_db failed: {}", e))?;
+        if incremental {
+            emit_progress(&window, 5, "Keeping existing games…")?;
+        } else {
+            emit_progress(&window, 5, "Removing old games…")?;
+            data::drop_games().map_err(|e| AppError::db(format!("drop_games failed: {}", e)))?;
+        }
 
         emit_progress(&window, 15, "Creating DB tables…")?;
-        data::setup().map_err(|e| format!("setup failed: {}", e))?;
+        data::setup().map_err(|e| AppError::db(format!("setup failed: {}", e)))?;
 
         emit_progress(&window, 25, "Populating consoles…")?;
-        data::console_fill().map_err(|e| format!("console_fill failed: {}", e))?;
+        data::console_fill().map_err(|e| AppError::db(format!("console_fill failed: {}", e)))?;
 
         // Scrape = 30..100 with per-console progress
         emit_progress(&window, 30, "Scraping…")?;
-        data::scrape_with_progress(|pct, msg| {
-            // pct is already 30..100
-            let _ = emit_progress(&window, pct, msg);
+        let summary = data::scrape_with_progress(incremental, |p| {
+            // percent is already 30..100
+            let _ = emit_payload(
+                &window,
+                StartupProgressPayload {
+                    percent: p.percent,
+                    message: p.message,
+                    console: p.console,
+                    console_games: p.console_games,
+                    total_games: p.total_games,
+                },
+            );
         })
-        .map_err(|e| format!("scrape failed: {}", e))?;
+        .map_err(|e| AppError::network(format!("scrape failed: {}", e)))?;
+
+        if summary.cancelled {
+            let _ = window.emit(
+                "startup-cancelled",
+                StartupCancelledPayload {
+                    scraped: summary.scraped,
+                    total_games: summary.total_games,
+                },
+            );
+            return Ok(summary);
+        }
+
+        if summary.scraped > 0 {
+            write_setting("last_scrape_at", &now_unix().to_string())?;
+        }
+
+        if read_bool_setting("compact_after_scrape", false)? {
+            emit_progress(&window, 99, "Compacting database…")?;
+            maintenance::compact()?;
+        }
 
-        emit_progress(&window, 100, "Done!")?;
-        Ok(())
+        let done = if summary.failed.is_empty() {
+            "Done!".to_string()
+        } else {
+            format!("Done with errors: {} consoles failed", summary.failed.len())
+        };
+        emit_progress(&window, 100, done)?;
+        Ok(summary)
     });
 
-    task.await.map_err(|e| e.to_string())?
+    task.await.map_err(|e| AppError::internal(e.to_string()))?
 }
 
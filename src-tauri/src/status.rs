@@ -1,27 +1,117 @@
 use reqwest::blocking::Client;
-use std::time::Duration;
+use serde::Serialize;
+use std::time::{Duration, Instant};
 
-#[tauri::command]
-pub fn network_check() -> Result<bool, String> {
+use crate::error::AppError;
+use crate::net::{client_builder, mirror_base};
+use crate::db;
 
-    let url = "https://myrient.erista.me/";
+const DEFAULT_MIRROR: &str = "https://myrient.erista.me/";
 
-    let client = Client::builder()
-        .timeout(Duration::from_secs(5))
-        .build()
-        .map_err(|e| format!("Client build failed: {}", e))?;
+#[derive(Serialize, Clone, Debug)]
+pub struct NetworkStatus {
+    pub reachable: bool,
+    pub latency_ms: u64,
+    pub url: String,
+}
+
+/// Distinct `scheme://host/` bases of the configured console sources.
+fn console_hosts() -> Vec<String> {
+    let Ok(conn) = db::open() else {
+        return Vec::new();
+    };
+    let Ok(mut stmt) = conn.prepare("SELECT DISTINCT url FROM consoles") else {
+        return Vec::new();
+    };
+    let Ok(rows) = stmt.query_map([], |row| row.get::<_, String>(0)) else {
+        return Vec::new();
+    };
 
-    // HEAD first
-    if let Ok(resp) = client.head(url).send() {
-        return Ok(resp.status().is_success());
+    let mut hosts: Vec<String> = Vec::new();
+    for url in rows.flatten() {
+        let Ok(parsed) = reqwest::Url::parse(&url) else {
+            continue;
+        };
+        let Some(host) = parsed.host_str() else {
+            continue;
+        };
+        let base = match parsed.port() {
+            Some(port) => format!("{}://{}:{}/", parsed.scheme(), host, port),
+            None => format!("{}://{}/", parsed.scheme(), host),
+        };
+        if !hosts.contains(&base) {
+            hosts.push(base);
+        }
     }
+    hosts
+}
+
+fn check_url(client: &Client, url: &str) -> NetworkStatus {
+    let started = Instant::now();
 
-    // fallback to GET
-    let resp = client
-        .get(url)
-        .send()
-        .map_err(|e| format!("Request failed: {}", e))?;
+    // HEAD first, fallback to GET
+    let reachable = match client.head(url).send() {
+        Ok(resp) => resp.status().is_success(),
+        Err(_) => client
+            .get(url)
+            .send()
+            .map(|resp| resp.status().is_success())
+            .unwrap_or(false),
+    };
 
-    Ok(resp.status().is_success())
+    NetworkStatus {
+        reachable,
+        latency_ms: started.elapsed().as_millis() as u64,
+        url: url.to_string(),
+    }
 }
 
+/// Checks every candidate base URL (defaults to the mirror, else the console source hosts) in parallel
+/// and returns the fastest reachable one, or the first candidate if none respond.
+#[tauri::command]
+pub fn network_check(candidates: Option<Vec<String>>) -> Result<NetworkStatus, AppError> {
+    let mut candidates: Vec<String> = candidates
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|c| !c.trim().is_empty())
+        .collect();
+    if candidates.is_empty() {
+        // With a preferred mirror set, that's the only host requests go to
+        candidates = match mirror_base()? {
+            Some(base) => vec![base],
+            None => console_hosts(),
+        };
+    }
+    if candidates.is_empty() {
+        candidates.push(DEFAULT_MIRROR.to_string());
+    }
+
+    let client = client_builder()?
+        .timeout(Duration::from_secs(5))
+        .build()
+        .map_err(|e| AppError::network(format!("Client build failed: {}", e)))?;
+
+    let results: Vec<NetworkStatus> = std::thread::scope(|scope| {
+        let handles: Vec<_> = candidates
+            .iter()
+            .map(|url| {
+                let client = &client;
+                scope.spawn(move || check_url(client, url))
+            })
+            .collect();
+
+        handles.into_iter().filter_map(|h| h.join().ok()).collect()
+    });
+
+    let fastest = results
+        .iter()
+        .filter(|r| r.reachable)
+        .min_by_key(|r| r.latency_ms)
+        .cloned();
+
+    Ok(fastest.unwrap_or(NetworkStatus {
+        reachable: false,
+        latency_ms: 0,
+        url: candidates[0].clone(),
+    }))
+}